@@ -0,0 +1,228 @@
+//! Mock VRF-style randomness oracle for local testing
+//! A consumer program records a request keyed by its own account and commit slot, an
+//! off-chain fulfiller writes the randomness back, and the consumer reads the fulfilled request.
+//! Keying by the commit slot lets a consumer account that is closed and recreated request again.
+
+use anchor_lang::prelude::*;
+
+declare_id!("BMZmFwTjsCaRXw7a9FxSeSdtLCTRnCLvJYG3RgFSzymE");
+
+pub const ORACLE_SEED: &[u8] = b"oracle";
+pub const REQUEST_SEED: &[u8] = b"request";
+
+#[program]
+pub mod mock_oracle {
+    use super::*;
+
+    /// Initialize the oracle with the fulfiller authority
+    pub fn initialize_oracle(ctx: Context<InitializeOracle>) -> Result<()> {
+        let oracle = &mut ctx.accounts.oracle;
+        oracle.authority = ctx.accounts.authority.key();
+        oracle.request_count = 0;
+        oracle.bump = ctx.bumps.oracle;
+        Ok(())
+    }
+
+    /// Request randomness for a consumer account (roll session or battle) committed at `commit_slot`
+    pub fn request_randomness(
+        ctx: Context<RequestRandomness>,
+        requester: Pubkey,
+        commit_slot: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let oracle = &mut ctx.accounts.oracle;
+        let request = &mut ctx.accounts.request;
+
+        request.requester = requester;
+        request.request_slot = clock.slot;
+        request.fulfilled = false;
+        request.randomness = [0u8; 32];
+        request.fulfilled_slot = 0;
+        request.bump = ctx.bumps.request;
+        request.commit_slot = commit_slot;
+
+        oracle.request_count = oracle.request_count.saturating_add(1);
+
+        emit!(RandomnessRequested {
+            request: request.key(),
+            requester,
+            slot: clock.slot,
+        });
+
+        Ok(())
+    }
+
+    /// Write the randomness for a pending request (oracle authority only)
+    pub fn fulfill_randomness(ctx: Context<FulfillRandomness>, randomness: [u8; 32]) -> Result<()> {
+        let clock = Clock::get()?;
+        let request = &mut ctx.accounts.request;
+
+        request.fulfill(randomness, clock.slot)?;
+
+        emit!(RandomnessFulfilled {
+            request: request.key(),
+            requester: request.requester,
+            randomness,
+            slot: clock.slot,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeOracle<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Oracle::LEN,
+        seeds = [ORACLE_SEED],
+        bump
+    )]
+    pub oracle: Account<'info, Oracle>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(requester: Pubkey, commit_slot: u64)]
+pub struct RequestRandomness<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [ORACLE_SEED],
+        bump = oracle.bump
+    )]
+    pub oracle: Account<'info, Oracle>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = RandomnessRequest::LEN,
+        seeds = [REQUEST_SEED, requester.as_ref(), &commit_slot.to_le_bytes()],
+        bump
+    )]
+    pub request: Account<'info, RandomnessRequest>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FulfillRandomness<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [ORACLE_SEED],
+        bump = oracle.bump,
+        constraint = oracle.authority == authority.key() @ OracleError::Unauthorized
+    )]
+    pub oracle: Account<'info, Oracle>,
+
+    #[account(
+        mut,
+        seeds = [REQUEST_SEED, request.requester.as_ref(), &request.commit_slot.to_le_bytes()],
+        bump = request.bump
+    )]
+    pub request: Account<'info, RandomnessRequest>,
+}
+
+#[account]
+pub struct Oracle {
+    pub authority: Pubkey,
+    pub request_count: u64,
+    pub bump: u8,
+}
+
+impl Oracle {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // authority
+        8 +  // request_count
+        1;   // bump
+}
+
+/// Consumers rely on the field order up to `randomness`
+#[account]
+pub struct RandomnessRequest {
+    pub requester: Pubkey,
+    pub request_slot: u64,
+    pub fulfilled: bool,
+    pub randomness: [u8; 32],
+    pub fulfilled_slot: u64,
+    pub bump: u8,
+    pub commit_slot: u64, // Requester's commit slot, part of the request's seeds
+}
+
+impl RandomnessRequest {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // requester
+        8 +  // request_slot
+        1 +  // fulfilled
+        32 + // randomness
+        8 +  // fulfilled_slot
+        1 +  // bump
+        8;   // commit_slot
+
+    pub fn fulfill(&mut self, randomness: [u8; 32], slot: u64) -> Result<()> {
+        require!(!self.fulfilled, OracleError::AlreadyFulfilled);
+        self.fulfilled = true;
+        self.randomness = randomness;
+        self.fulfilled_slot = slot;
+        Ok(())
+    }
+}
+
+#[error_code]
+pub enum OracleError {
+    #[msg("Unauthorized")]
+    Unauthorized,
+
+    #[msg("Randomness already fulfilled")]
+    AlreadyFulfilled,
+}
+
+#[event]
+pub struct RandomnessRequested {
+    pub request: Pubkey,
+    pub requester: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+pub struct RandomnessFulfilled {
+    pub request: Pubkey,
+    pub requester: Pubkey,
+    pub randomness: [u8; 32],
+    pub slot: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fulfill_once() {
+        let mut request = RandomnessRequest {
+            requester: Pubkey::new_unique(),
+            request_slot: 10,
+            fulfilled: false,
+            randomness: [0u8; 32],
+            fulfilled_slot: 0,
+            bump: 255,
+            commit_slot: 10,
+        };
+
+        request.fulfill([7u8; 32], 12).unwrap();
+        assert!(request.fulfilled);
+        assert_eq!(request.randomness, [7u8; 32]);
+        assert_eq!(request.fulfilled_slot, 12);
+
+        // A fulfilled request can't be overwritten
+        assert!(request.fulfill([8u8; 32], 13).is_err());
+        assert_eq!(request.randomness, [7u8; 32]);
+    }
+}
@@ -7,42 +7,175 @@ pub const BATTLE_SEED: &[u8] = b"battle";
 pub const ROLL_SESSION_SEED: &[u8] = b"roll";
 pub const ENEMY_TEMPLATE_SEED: &[u8] = b"enemy_template";
 pub const TREASURY_SEED: &[u8] = b"treasury";
+pub const VAULT_SEED: &[u8] = b"vault"; // System-owned PDA holding the treasury's lamports
+pub const TREASURY_TOKEN_SEED: &[u8] = b"treasury_token"; // Per-mint token account owned by the vault
+pub const LOOT_TABLE_SEED: &[u8] = b"loot_table";
+pub const ITEM_SEED: &[u8] = b"item";
+pub const GAME_CONFIG_SEED: &[u8] = b"game_config";
+pub const ODDS_TABLE_SEED: &[u8] = b"odds_table";
+pub const BANNER_SEED: &[u8] = b"banner";
 
 // Random number generation (matching ponzimon-program)
 pub const MIN_RANDOMNESS_DELAY_SLOTS: u64 = 2;
 pub const CANCEL_TIMEOUT_SLOTS: u64 = 24;
+// A session whose reveal slot was skipped by its leader can move its commit forward this many
+// times. A skipped slot never had a hash, so nobody saw its outcome. Expired hashes did exist,
+// so those sessions can't recommit and cancelling them keeps the fee.
+pub const MAX_RECOMMITS: u8 = 1;
 
-// Game economics (in lamports)
+// Randomness providers, chosen per game in GameConfig
+pub const RANDOMNESS_PROVIDER_SLOT_HASHES: u8 = 0;
+pub const RANDOMNESS_PROVIDER_ORACLE: u8 = 1;
+pub const ORACLE_DISCRIMINATOR_LEN: usize = 8;
+// Anchor discriminator of the mock oracle's `RandomnessRequest`, the default OracleLayout
+pub const MOCK_ORACLE_REQUEST_DISCRIMINATOR: [u8; ORACLE_DISCRIMINATOR_LEN] = [244, 231, 228, 160, 148, 28, 17, 184];
+
+// Player secret commit-reveal: the secret's hash is committed at roll/battle start and the
+// secret is revealed at roll fulfill or the first battle turn, then mixed into the seed.
+// A session whose secret is never revealed can only be cancelled after CANCEL_TIMEOUT_SLOTS.
+// The entry cost is refunded only if the program can show the randomness never existed (a
+// skipped reveal slot still in SlotHashes, or an unfulfilled oracle request). Otherwise the
+// player may already know the outcome, so the fee is kept and abandoning it always costs it.
+pub const PLAYER_SECRET_DOMAIN: &[u8] = b"player_secret";
+
+// Settled battles can be closed by anyone after this delay (rent goes to the hero owner)
+pub const BATTLE_CLEANUP_DELAY_SECONDS: i64 = 7 * 24 * 60 * 60; // 7 days
+
+// Game economics (in lamports), defaults for GameConfig
 pub const HERO_PURCHASE_COST: u64 = 1_000_000_000; // 1 SOL
 pub const ROLL_COST: u64 = 100_000_000; // 0.1 SOL
 pub const BATTLE_ENTRY_COST: u64 = 50_000_000; // 0.05 SOL
+pub const ROLL_BATCH_COST: u64 = 900_000_000; // 0.9 SOL for ROLL_BATCH_SIZE rolls
+pub const TREASURY_RESERVE: u64 = 5_000_000_000; // 5 SOL kept for refunds, pending escrow first, then the dev bucket
+
+// SPL token payments: prices are set per game in GameConfig, revenue is tracked per mint
+pub const MAX_PAYMENT_MINTS: usize = 4;
 
-// Hero system
+// Revenue split: SOL fees are divided into buckets claimable by their recipients
+pub const REVENUE_BUCKET_DEV: usize = 0;
+pub const REVENUE_BUCKET_PRIZE_POOL: usize = 1; // Seasonal prizes
+pub const REVENUE_BUCKET_REFERRAL_POOL: usize = 2; // Referral campaigns, per-spend rewards come off the top
+pub const REVENUE_BUCKET_RESERVE: usize = 3; // Burn or reserve, also receives rounding dust
+pub const REVENUE_BUCKET_COUNT: usize = 4;
+pub const REVENUE_SHARE_TOTAL: u16 = 10000; // Bucket shares are basis points summing to this
+
+// Referrals: referrers earn this share of their referees' SOL spending (GameConfig default),
+// taken off the top of each fee before the split. Token payments earn no referral rewards.
+pub const REFERRAL_BPS: u16 = 500; // 5%
+
+// Hero system (points, XP and max level are GameConfig defaults)
 pub const BASE_ATTRIBUTE_POINTS: u16 = 10;
 pub const ATTRIBUTE_POINTS_PER_LEVEL: u16 = 5;
 pub const XP_PER_LEVEL_BASE: u32 = 1000;
 pub const MAX_LEVEL: u16 = 100;
 pub const MAX_HEROES_PER_PLAYER: u8 = 5;
 
-// Battle system
+// Gold: soft currency earned from victories and spent on items, GameConfig defaults
+pub const GOLD_PER_ENEMY_LEVEL: u32 = 10;
+pub const ITEM_SHOP_COST: u64 = 50; // Common item from the shop
+pub const ITEM_REPAIR_COST: u32 = 1; // Per durability point restored
+pub const ITEM_UPGRADE_COST: u64 = 100; // Per rarity step, times the rarity reached
+pub const ELEMENT_CHANGE_COST: u64 = 50; // Changing a hero's skill element
+
+// Hero classes
+pub const HERO_CLASS_WARRIOR: u8 = 0;
+pub const HERO_CLASS_MAGE: u8 = 1;
+pub const HERO_CLASS_ROGUE: u8 = 2;
+pub const HERO_CLASS_CLERIC: u8 = 3;
+pub const HERO_CLASS_COUNT: usize = 4;
+
+// Starting attributes per class, same 80 point total as the old base hero
+// [strength, dexterity, vitality, intelligence, wisdom, agility, precision, luck]
+pub const CLASS_STARTING_ATTRIBUTES: [[u16; 8]; HERO_CLASS_COUNT] = [
+    [16, 10, 16, 4, 6, 10, 10, 8],  // Warrior
+    [4, 8, 8, 18, 16, 10, 10, 6],   // Mage
+    [12, 16, 8, 6, 6, 14, 12, 6],   // Rogue
+    [8, 8, 12, 10, 16, 8, 6, 12],   // Cleric
+];
+
+// Free attribute points granted by the class on every level up
+pub const CLASS_LEVEL_BONUS: [[u16; 8]; HERO_CLASS_COUNT] = [
+    [1, 0, 1, 0, 0, 0, 0, 0], // Warrior: strength, vitality
+    [0, 0, 0, 1, 1, 0, 0, 0], // Mage: intelligence, wisdom
+    [0, 1, 0, 0, 0, 1, 0, 0], // Rogue: dexterity, agility
+    [0, 0, 1, 0, 1, 0, 0, 0], // Cleric: vitality, wisdom
+];
+
+// Default skill element per class
+pub const CLASS_SKILL_ELEMENTS: [u8; HERO_CLASS_COUNT] = [
+    ELEMENT_NEUTRAL, // Warrior
+    ELEMENT_FIRE,    // Mage
+    ELEMENT_AIR,     // Rogue
+    ELEMENT_LIGHT,   // Cleric
+];
+
+// Class skills (ACTION_SKILL)
+pub const POWER_STRIKE_MULTIPLIER: u32 = 15000; // Warrior: 150% strength damage
+pub const BACKSTAB_CRIT_MULTIPLIER: u32 = 200; // Rogue: 2% crit chance per precision point
+pub const HEAL_WISDOM_MULTIPLIER: u32 = 3; // Cleric: heals wisdom * 3 HP
+
+// Battle system, defaults for GameConfig
 pub const BASE_HP_MULTIPLIER: u16 = 10; // HP = vitality * multiplier
 pub const BASE_DAMAGE_REDUCTION: u16 = 2; // damage = max(1, damage - defense/2)
 pub const CRITICAL_MULTIPLIER: u16 = 2;
 pub const BASE_ESCAPE_CHANCE: u16 = 3000; // 30% out of 10000
 pub const AGILITY_ESCAPE_BONUS: u16 = 100; // 1% per agility point difference
+pub const MIN_ESCAPE_CHANCE: u16 = 1000; // 10%
+pub const MAX_ESCAPE_CHANCE: u16 = 8000; // 80%
+pub const MIN_SPEED_ADVANTAGE: u16 = 2000; // Dexterity must exceed the opponent's by 20% to fill the speed gauge
+pub const SPEED_GAUGE_FULL: u16 = 10000; // A full gauge grants one extra action
 
-// Roll rewards (XP amounts)
-pub const ROLL_REWARDS: [u32; 6] = [
-    100,  // Common
-    200,  // Uncommon
-    500,  // Rare
-    1000, // Epic
-    2000, // Legendary
-    5000, // Mythic
-];
+// Combatants in a turn's action sequence
+pub const COMBATANT_HERO: u8 = 0;
+pub const COMBATANT_ENEMY: u8 = 1;
+pub const BATTLE_ACTION_LOG_LEN: usize = 64; // 2 bits per action, covers all 256 turns
+
+// Roll odds tables (rarity weights and XP rewards are set by the admin)
+pub const ODDS_TOTAL_WEIGHT: u16 = 10000; // Weights of an odds table must sum to this
+pub const STANDARD_ODDS_TABLE_ID: u32 = 0; // Table used by regular rolls
+
+// Roll batches: one reveal resolves every roll, at least one result is Rare or better
+pub const ROLL_BATCH_SIZE: usize = 10;
+pub const ROLL_BATCH_GUARANTEED_RARITY: u8 = RARITY_RARE;
+
+// Roll pity (GameConfig defaults): consecutive rolls below the floor raise the odds
+pub const PITY_RARITY_FLOOR: u8 = RARITY_EPIC;
+pub const PITY_HARD_LIMIT: u16 = 60; // After this many misses the next roll is at least Epic
+pub const PITY_SOFT_START: u16 = 40; // Misses before soft pity kicks in
+pub const PITY_SOFT_BONUS: u16 = 500; // +5% chance per miss past the soft start to reach the floor
 
 // Equipment system
-pub const MAX_ITEMS_PER_HERO: u8 = 3; // Weapon, Armor, Accessory
+pub const MAX_ITEMS_PER_HERO: u8 = 3; // Weapon, Armor, Accessory; fixed by Hero's slots, not tunable
+pub const MAX_ITEM_DURABILITY: u16 = 100;
+pub const ITEM_WEAR_PER_BATTLE: u16 = 5; // Lost by each equipped item when a battle is settled
+
+// Attribute bonus granted by an item, indexed by rarity
+pub const ITEM_BONUS_BY_RARITY: [u16; 6] = [
+    1,  // Common
+    2,  // Uncommon
+    4,  // Rare
+    7,  // Epic
+    11, // Legendary
+    16, // Mythic
+];
+
+// Resistance to its own element granted by an elemental item, indexed by rarity
+pub const ITEM_RESISTANCE_BY_RARITY: [i16; 6] = [
+    500,  // Common
+    1000, // Uncommon
+    1500, // Rare
+    2000, // Epic
+    2500, // Legendary
+    3000, // Mythic
+];
+
+// Loot system
+pub const MAX_LOOT_ENTRIES: usize = 8;
+
+// Luck modifier (GameConfig defaults): max * luck / (luck + half point), never above max.
+// It shifts roll rarity thresholds up and adds to loot drop and rarity upgrade chances.
+pub const LUCK_HALF_POINT: u16 = 50; // Luck needed for half of the maximum bonus
+pub const MAX_LUCK_BONUS: u16 = 2000; // Hard cap, out of 10000
 
 // Enemy AI flags
 pub const AI_AGGRESSIVE: u8 = 1;
@@ -66,12 +199,27 @@ pub const RARITY_RARE: u8 = 2;
 pub const RARITY_EPIC: u8 = 3;
 pub const RARITY_LEGENDARY: u8 = 4;
 pub const RARITY_MYTHIC: u8 = 5;
+pub const RARITY_COUNT: usize = 6;
 
 // Item types
 pub const ITEM_TYPE_WEAPON: u8 = 0;
 pub const ITEM_TYPE_ARMOR: u8 = 1;
 pub const ITEM_TYPE_ACCESSORY: u8 = 2;
 
+// Elements
+pub const ELEMENT_NEUTRAL: u8 = 0;
+pub const ELEMENT_FIRE: u8 = 1;
+pub const ELEMENT_WATER: u8 = 2;
+pub const ELEMENT_EARTH: u8 = 3;
+pub const ELEMENT_AIR: u8 = 4;
+pub const ELEMENT_LIGHT: u8 = 5;
+pub const ELEMENT_DARK: u8 = 6;
+pub const ELEMENT_COUNT: usize = 7;
+pub const ELEMENT_ADVANTAGE_MULTIPLIER: u16 = 15000; // 150% out of 10000, GameConfig default
+pub const ELEMENT_DISADVANTAGE_MULTIPLIER: u16 = 7500; // 75% out of 10000, GameConfig default
+pub const MAX_ELEMENT_RESISTANCE: i16 = 9000; // At most 90% damage reduction
+pub const MIN_ELEMENT_RESISTANCE: i16 = -10000; // At most double damage (weakness)
+
 // Battle actions
 pub const ACTION_ATTACK: u8 = 0;
 pub const ACTION_DEFEND: u8 = 1;
@@ -85,4 +85,118 @@ pub enum RpgError {
 
     #[msg("Enemy is dead")]
     EnemyIsDead,
+
+    #[msg("Battle already settled")]
+    BattleAlreadySettled,
+
+    #[msg("Battle not settled yet")]
+    BattleNotSettled,
+
+    #[msg("Battle cleanup delay not expired")]
+    BattleCleanupNotExpired,
+
+    #[msg("Invalid loot table")]
+    InvalidLootTable,
+
+    #[msg("Loot item account missing or invalid")]
+    InvalidLootItemAccount,
+
+    #[msg("Battle turn limit reached")]
+    BattleTurnLimitReached,
+
+    #[msg("Invalid element")]
+    InvalidElement,
+
+    #[msg("Invalid hero class")]
+    InvalidHeroClass,
+
+    #[msg("Invalid game config")]
+    InvalidGameConfig,
+
+    #[msg("Slot hash expired from the SlotHashes sysvar")]
+    SlotHashExpired,
+
+    #[msg("Slot hash not available yet")]
+    SlotHashInFuture,
+
+    #[msg("Invalid randomness provider")]
+    InvalidRandomnessProvider,
+
+    #[msg("Oracle request account missing or invalid")]
+    InvalidOracleRequest,
+
+    #[msg("Oracle request was made before the commit")]
+    StaleOracleRequest,
+
+    #[msg("Oracle randomness not fulfilled yet")]
+    OracleRandomnessPending,
+
+    #[msg("Revealed secret does not match the commitment")]
+    InvalidSecretReveal,
+
+    #[msg("Battle does not use per-turn randomness")]
+    PerTurnRandomnessDisabled,
+
+    #[msg("No action committed for this turn")]
+    ActionNotCommitted,
+
+    #[msg("An action is already committed for this turn")]
+    ActionAlreadyCommitted,
+
+    #[msg("Action does not match the committed action")]
+    ActionCommitMismatch,
+
+    #[msg("Invalid odds table")]
+    InvalidOddsTable,
+
+    #[msg("Invalid banner")]
+    InvalidBanner,
+
+    #[msg("Banner is not live")]
+    BannerNotLive,
+
+    #[msg("Insufficient treasury funds")]
+    InsufficientTreasuryFunds,
+
+    #[msg("Reveal slot hash is still available")]
+    RevealSlotAvailable,
+
+    #[msg("Recommit limit reached")]
+    RecommitLimitReached,
+
+    #[msg("Token payments are disabled")]
+    TokenPaymentDisabled,
+
+    #[msg("Invalid payment mint")]
+    InvalidPaymentMint,
+
+    #[msg("Invalid payment token account")]
+    InvalidPaymentAccount,
+
+    #[msg("Too many payment mints")]
+    TooManyPaymentMints,
+
+    #[msg("Insufficient gold")]
+    InsufficientGold,
+
+    #[msg("Invalid revenue split")]
+    InvalidRevenueSplit,
+
+    #[msg("Nothing to claim")]
+    NothingToClaim,
+
+    #[msg("Invalid referrer")]
+    InvalidReferrer,
+
+    #[msg("Treasury is not in the pre-vault layout")]
+    InvalidTreasuryLayout,
+
+    #[msg("Banner rolls can only be paid in SOL")]
+    BannerRequiresSol,
+
+    #[msg("Item is not damaged")]
+    ItemNotDamaged,
+
+    #[msg("Account is not in its legacy layout")]
+    InvalidLegacyLayout,
 }
\ No newline at end of file
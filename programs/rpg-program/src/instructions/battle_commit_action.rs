@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+use crate::utils::validation::*;
+
+#[derive(Accounts)]
+pub struct BattleCommitAction<'info> {
+    pub player_authority: Signer<'info>,
+
+    #[account(
+        seeds = [PLAYER_SEED, player_authority.key().as_ref()],
+        bump = player.bump,
+        constraint = player.authority == player_authority.key() @ RpgError::Unauthorized
+    )]
+    pub player: Account<'info, Player>,
+
+    #[account(
+        seeds = [HERO_SEED, player.key().as_ref(), &hero.index.to_le_bytes()],
+        bump = hero.bump,
+        constraint = hero.owner == player_authority.key() @ RpgError::Unauthorized,
+        constraint = hero.player == player.key() @ RpgError::HeroNotFound
+    )]
+    pub hero: Account<'info, Hero>,
+
+    #[account(
+        mut,
+        seeds = [BATTLE_SEED, hero.key().as_ref(), &battle.nonce.to_le_bytes()],
+        bump = battle.bump,
+        constraint = battle.hero == hero.key() @ RpgError::BattleNotFound,
+        constraint = battle.is_active() @ RpgError::BattleAlreadyCompleted,
+        constraint = battle.per_turn_randomness @ RpgError::PerTurnRandomnessDisabled
+    )]
+    pub battle: Account<'info, Battle>,
+}
+
+pub fn battle_commit_action(ctx: Context<BattleCommitAction>, action: u8) -> Result<()> {
+    let clock = Clock::get()?;
+    let battle = &mut ctx.accounts.battle;
+
+    // Validate action
+    validate_battle_action(action)?;
+
+    // One commitment per turn, resolved by battle_turn once its slot hash exists
+    require!(battle.committed_action.is_none(), RpgError::ActionAlreadyCommitted);
+
+    battle.committed_action = Some(action);
+    battle.action_commit_slot = clock.slot;
+
+    emit!(BattleActionCommitted {
+        battle: battle.key(),
+        turn: battle.turn,
+        action,
+        slot: clock.slot,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct BattleActionCommitted {
+    pub battle: Pubkey,
+    pub turn: u8,
+    pub action: u8,
+    pub slot: u64,
+}
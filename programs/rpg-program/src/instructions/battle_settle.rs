@@ -2,6 +2,9 @@ use anchor_lang::prelude::*;
 use crate::constants::*;
 use crate::errors::RpgError;
 use crate::state::*;
+use crate::utils::rng::*;
+use crate::utils::items::*;
+use crate::utils::math::calculate_gold_reward;
 
 #[derive(Accounts)]
 pub struct BattleSettle<'info> {
@@ -37,24 +40,123 @@ pub struct BattleSettle<'info> {
         bump = battle.bump,
         constraint = battle.hero == hero.key() @ RpgError::BattleNotFound,
         constraint = battle.enemy_template == enemy_template.key() @ RpgError::BattleNotFound,
-        constraint = !battle.is_active() @ RpgError::InvalidBattleAction
+        constraint = !battle.is_active() @ RpgError::InvalidBattleAction,
+        constraint = !battle.settled @ RpgError::BattleAlreadySettled
     )]
     pub battle: Account<'info, Battle>,
+
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(
+        seeds = [LOOT_TABLE_SEED, enemy_template.key().as_ref()],
+        bump = loot_table.bump
+    )]
+    pub loot_table: Option<Account<'info, LootTable>>,
+
+    /// CHECK: Item PDA, only created when the loot roll drops something
+    #[account(mut)]
+    pub loot_item: Option<UncheckedAccount<'info>>,
+
+    /// Equipped items wear down every battle, each is required while its slot is filled
+    #[account(
+        mut,
+        constraint = Some(equipped_weapon.key()) == hero.equipped_weapon @ RpgError::ItemNotFound
+    )]
+    pub equipped_weapon: Option<Account<'info, Item>>,
+
+    #[account(
+        mut,
+        constraint = Some(equipped_armor.key()) == hero.equipped_armor @ RpgError::ItemNotFound
+    )]
+    pub equipped_armor: Option<Account<'info, Item>>,
+
+    #[account(
+        mut,
+        constraint = Some(equipped_accessory.key()) == hero.equipped_accessory @ RpgError::ItemNotFound
+    )]
+    pub equipped_accessory: Option<Account<'info, Item>>,
+
+    pub system_program: Program<'info, System>,
 }
 
 pub fn battle_settle(ctx: Context<BattleSettle>) -> Result<()> {
+    let clock = Clock::get()?;
     let player = &mut ctx.accounts.player;
     let hero = &mut ctx.accounts.hero;
     let enemy_template = &ctx.accounts.enemy_template;
-    let battle = &ctx.accounts.battle;
+    let battle = &mut ctx.accounts.battle;
+
+    // Rewards can only be claimed once per battle
+    battle.mark_settled(clock.unix_timestamp)?;
 
     let mut xp_gained = 0u32;
+    let mut gold_gained = 0u64;
     let victory = battle.state == BATTLE_STATE_HERO_WON;
 
     if victory {
         // Hero won - award XP
         xp_gained = enemy_template.xp_reward;
         hero.add_xp(xp_gained);
+
+        // Award gold scaled by the enemy's level
+        gold_gained = calculate_gold_reward(&ctx.accounts.game_config.economy, enemy_template.level);
+        player.add_gold(gold_gained);
+
+        // Roll the enemy's loot table, luck improves the odds
+        if let Some(loot_table) = &ctx.accounts.loot_table {
+            let luck = battle.hero_attributes.luck;
+            if let Some((item_type, rarity, element)) =
+                roll_loot(
+                    &battle.rng_seed,
+                    battle.turn,
+                    loot_table.active_entries(),
+                    luck,
+                    &ctx.accounts.game_config.economy,
+                )
+            {
+                let loot_item = ctx
+                    .accounts
+                    .loot_item
+                    .as_ref()
+                    .ok_or(RpgError::InvalidLootItemAccount)?;
+                let battle_key = battle.key();
+                let item = Item::generate(hero.key(), item_type, rarity, element, clock.unix_timestamp);
+                let item_key = create_item(
+                    &ctx.accounts.player_authority.to_account_info(),
+                    &loot_item.to_account_info(),
+                    &ctx.accounts.system_program.to_account_info(),
+                    ctx.program_id,
+                    hero,
+                    item,
+                )?;
+
+                emit!(LootDropped {
+                    hero: hero.key(),
+                    battle: battle_key,
+                    item: item_key,
+                    item_type,
+                    rarity,
+                    element,
+                });
+            }
+        }
+    }
+
+    // Win or lose, the hero's gear takes wear that repairs restore
+    let equipped = [
+        (hero.equipped_weapon, &mut ctx.accounts.equipped_weapon),
+        (hero.equipped_armor, &mut ctx.accounts.equipped_armor),
+        (hero.equipped_accessory, &mut ctx.accounts.equipped_accessory),
+    ];
+    for (slot, item) in equipped {
+        match item {
+            Some(item) => item.wear(ITEM_WEAR_PER_BATTLE),
+            None => require!(slot.is_none(), RpgError::ItemNotFound),
+        }
     }
 
     // Update battle statistics
@@ -68,6 +170,7 @@ pub fn battle_settle(ctx: Context<BattleSettle>) -> Result<()> {
         battle: battle.key(),
         victory,
         xp_gained,
+        gold_gained,
         hero_hp_remaining: battle.hero_hp,
         turns_taken: battle.turn,
     });
@@ -83,6 +186,17 @@ pub struct BattleSettled {
     pub battle: Pubkey,
     pub victory: bool,
     pub xp_gained: u32,
+    pub gold_gained: u64,
     pub hero_hp_remaining: u32,
     pub turns_taken: u8,
-}
\ No newline at end of file
+}
+
+#[event]
+pub struct LootDropped {
+    pub hero: Pubkey,
+    pub battle: Pubkey,
+    pub item: Pubkey,
+    pub item_type: u8,
+    pub rarity: u8,
+    pub element: u8,
+}
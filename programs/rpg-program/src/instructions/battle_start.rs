@@ -1,13 +1,15 @@
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
+use anchor_spl::token::{Token, TokenAccount};
 use crate::constants::*;
 use crate::errors::RpgError;
 use crate::state::*;
+use crate::utils::math::{calculate_hp, calculate_referral_reward};
 use crate::utils::validation::*;
-use crate::utils::math::*;
+use crate::utils::vault::TokenPaymentAccounts;
 
 #[derive(Accounts)]
-#[instruction(nonce: u64)]
+#[instruction(nonce: u64, secret_hash: [u8; 32], per_turn_randomness: bool)]
 pub struct BattleStart<'info> {
     #[account(mut)]
     pub player_authority: Signer<'info>,
@@ -44,6 +46,12 @@ pub struct BattleStart<'info> {
     )]
     pub battle: Account<'info, Battle>,
 
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
     #[account(
         mut,
         seeds = [TREASURY_SEED],
@@ -51,38 +59,131 @@ pub struct BattleStart<'info> {
     )]
     pub treasury: Account<'info, Treasury>,
 
+    #[account(
+        mut,
+        seeds = [VAULT_SEED],
+        bump = treasury.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    /// Player's token account, pass with the treasury token account to pay in the configured mint
+    #[account(
+        mut,
+        constraint = player_token_account.owner == player_authority.key() @ RpgError::InvalidPaymentAccount
+    )]
+    pub player_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_TOKEN_SEED, treasury_token_account.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// Equipped items add their bonuses to the snapshot, each is required while its slot is filled
+    #[account(
+        constraint = Some(equipped_weapon.key()) == hero.equipped_weapon @ RpgError::ItemNotFound
+    )]
+    pub equipped_weapon: Option<Account<'info, Item>>,
+
+    #[account(
+        constraint = Some(equipped_armor.key()) == hero.equipped_armor @ RpgError::ItemNotFound
+    )]
+    pub equipped_armor: Option<Account<'info, Item>>,
+
+    #[account(
+        constraint = Some(equipped_accessory.key()) == hero.equipped_accessory @ RpgError::ItemNotFound
+    )]
+    pub equipped_accessory: Option<Account<'info, Item>>,
+
     pub system_program: Program<'info, System>,
 }
 
-pub fn battle_start(ctx: Context<BattleStart>, nonce: u64) -> Result<()> {
+pub fn battle_start(
+    ctx: Context<BattleStart>,
+    nonce: u64,
+    secret_hash: [u8; 32],
+    per_turn_randomness: bool,
+) -> Result<()> {
     let clock = Clock::get()?;
     let player = &mut ctx.accounts.player;
     let hero = &ctx.accounts.hero;
     let enemy_template = &ctx.accounts.enemy_template;
     let battle = &mut ctx.accounts.battle;
     let treasury = &mut ctx.accounts.treasury;
+    let combat_config = ctx.accounts.game_config.combat;
 
-    // Validate sufficient funds
-    validate_sufficient_funds(
-        ctx.accounts.player_authority.lamports(),
-        BATTLE_ENTRY_COST,
-    )?;
-
-    // Transfer SOL to treasury
-    system_program::transfer(
-        CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            system_program::Transfer {
-                from: ctx.accounts.player_authority.to_account_info(),
-                to: treasury.to_account_info(),
-            },
-        ),
-        BATTLE_ENTRY_COST,
+    // Pay in the configured token when token accounts are passed, SOL otherwise
+    let token_accounts = TokenPaymentAccounts::from_optional(
+        &ctx.accounts.player_token_account,
+        &ctx.accounts.treasury_token_account,
+        &ctx.accounts.token_program,
     )?;
+    let (cost, payment_mint, referral_reward) = match token_accounts {
+        Some(token_accounts) => {
+            let token_payment = ctx
+                .accounts
+                .game_config
+                .token_payment
+                .ok_or(RpgError::TokenPaymentDisabled)?;
+            let cost = token_payment.battle_entry_cost;
+            token_accounts.collect(&ctx.accounts.player_authority, &token_payment.mint, cost)?;
+            treasury.escrow_token_entry(&token_payment.mint, cost)?;
+            (cost, Some(token_payment.mint), 0)
+        }
+        None => {
+            let cost = ctx.accounts.game_config.economy.battle_entry_cost;
+
+            // Validate sufficient funds
+            validate_sufficient_funds(
+                ctx.accounts.player_authority.lamports(),
+                cost,
+            )?;
+
+            // Transfer SOL to the treasury vault
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.player_authority.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                    },
+                ),
+                cost,
+            )?;
+
+            // Held in escrow until the randomness is revealed, a cancel refunds it
+            treasury.escrow_entry(cost);
+            player.add_sol_spent(cost);
+
+            // Referrers earn a share of SOL spending, credited once the entry resolves
+            let referral_reward = match player.referrer {
+                Some(_) => calculate_referral_reward(&ctx.accounts.game_config.economy, cost),
+                None => 0,
+            };
+            (cost, None, referral_reward)
+        }
+    };
+
+    // Snapshot the hero with its gear, worn items count for less and elemental items add resistances
+    let mut equipped = Vec::with_capacity(MAX_ITEMS_PER_HERO as usize);
+    for (slot, item) in [
+        (hero.equipped_weapon, &ctx.accounts.equipped_weapon),
+        (hero.equipped_armor, &ctx.accounts.equipped_armor),
+        (hero.equipped_accessory, &ctx.accounts.equipped_accessory),
+    ] {
+        match item {
+            Some(item) => equipped.push(&**item),
+            None => require!(slot.is_none(), RpgError::ItemNotFound),
+        }
+    }
+    let hero_attributes = hero.calculate_total_attributes(&equipped);
 
     // Calculate starting HP for hero and enemy
-    let hero_max_hp = hero.calculate_hp();
-    let enemy_max_hp = enemy_template.calculate_hp();
+    let hero_max_hp = calculate_hp(&combat_config, hero_attributes.vitality);
+    let enemy_max_hp = enemy_template.calculate_hp(&combat_config);
 
     // Initialize empty seed - will be filled when randomness is revealed
     let rng_seed = [0u8; 32];
@@ -94,19 +195,36 @@ pub fn battle_start(ctx: Context<BattleStart>, nonce: u64) -> Result<()> {
     battle.enemy_hp = enemy_max_hp;
     battle.hero_max_hp = hero_max_hp;
     battle.enemy_max_hp = enemy_max_hp;
+    battle.hero_class = hero.hero_class;
+    battle.hero_attributes = hero_attributes;
+    battle.enemy_attributes = enemy_template.base_attributes;
+    battle.hero_elemental = hero.calculate_elemental(&equipped);
+    battle.enemy_elemental = enemy_template.elemental;
+    battle.enemy_ai_flags = enemy_template.ai_flags;
+    battle.combat_config = combat_config;
     battle.rng_seed = rng_seed;
+    battle.per_turn_randomness = per_turn_randomness;
+    battle.committed_action = None;
+    battle.action_commit_slot = 0;
+    battle.turn_entropy = [0u8; 32];
     battle.turn = 0;
     battle.state = BATTLE_STATE_ACTIVE;
+    battle.action_log = [0u8; BATTLE_ACTION_LOG_LEN];
+    battle.log_hash = [0u8; 32];
     battle.nonce = nonce;
     battle.commit_slot = clock.slot;
+    battle.recommit_count = 0;
+    battle.randomness_provider = ctx.accounts.game_config.randomness_provider;
+    battle.oracle_program = ctx.accounts.game_config.oracle_program;
+    battle.secret_hash = secret_hash;
+    battle.entry_cost = cost;
+    battle.payment_mint = payment_mint;
+    battle.referral_reward = referral_reward;
     battle.created_at = clock.unix_timestamp;
+    battle.settled = false;
+    battle.settled_at = 0;
     battle.bump = ctx.bumps.battle;
-
-    // Update treasury stats
-    treasury.add_revenue(BATTLE_ENTRY_COST, crate::state::treasury::RevenueType::Battle);
-
-    // Update player stats
-    player.add_sol_spent(BATTLE_ENTRY_COST);
+    battle.oracle_layout = ctx.accounts.game_config.oracle_layout;
 
     emit!(BattleStarted {
         player: player.key(),
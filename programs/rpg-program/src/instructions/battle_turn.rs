@@ -1,11 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::sysvar::slot_hashes;
 use crate::constants::*;
 use crate::errors::RpgError;
 use crate::state::*;
 use crate::utils::validation::*;
-use crate::utils::rng::*;
-use crate::utils::math::*;
+use crate::utils::randomness::*;
+use crate::utils::combat::*;
 
 #[derive(Accounts)]
 pub struct BattleTurn<'info> {
@@ -45,14 +44,29 @@ pub struct BattleTurn<'info> {
     )]
     pub battle: Account<'info, Battle>,
 
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
     /// CHECK: Checked manually for slot hashes sysvar
     pub slot_hashes: AccountInfo<'info>,
+
+    /// CHECK: Owner and requester checked in utils::randomness, only used by the oracle provider
+    pub oracle_request: Option<UncheckedAccount<'info>>,
+
+    /// Referrer of the player, required when the entry earned them a reward
+    #[account(
+        mut,
+        constraint = Some(referrer.key()) == player.referrer @ RpgError::InvalidReferrer
+    )]
+    pub referrer: Option<Account<'info, Player>>,
 }
 
-pub fn battle_turn(ctx: Context<BattleTurn>, action: u8) -> Result<()> {
+pub fn battle_turn(ctx: Context<BattleTurn>, action: u8, secret: Option<[u8; 32]>) -> Result<()> {
     let clock = Clock::get()?;
-    let hero = &ctx.accounts.hero;
-    let enemy_template = &ctx.accounts.enemy_template;
     let battle = &mut ctx.accounts.battle;
 
     // Validate action
@@ -62,156 +76,70 @@ pub fn battle_turn(ctx: Context<BattleTurn>, action: u8) -> Result<()> {
     require!(battle.is_hero_alive(), RpgError::HeroIsDead);
     require!(battle.is_enemy_alive(), RpgError::EnemyIsDead);
 
-    // For the first turn, we need to establish randomness using slot hashes
+    // For the first turn, we need to establish randomness from the battle's provider
     if battle.turn == 0 {
-        // Validate randomness delay has passed
-        validate_randomness_delay(clock.slot, battle.commit_slot)?;
-
-        let reveal_slot = battle.commit_slot + MIN_RANDOMNESS_DELAY_SLOTS;
-
-        // Validate slot hashes sysvar
-        let sysvar_slot_history = &ctx.accounts.slot_hashes;
-        require!(
-            sysvar_slot_history.key == &slot_hashes::id(),
-            RpgError::InvalidSlotHashes
-        );
-
-        // Extract slot hash for randomness
-        let data = sysvar_slot_history.try_borrow_data()?;
-        let num_slot_hashes = u64::from_le_bytes(data[0..8].try_into().unwrap());
-        let mut pos = 8;
-        let mut found_hash = None;
-
-        for _ in 0..num_slot_hashes {
-            let slot = u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap());
-            pos += 8;
-            let hash = &data[pos..pos + 32];
-            if slot == reveal_slot {
-                found_hash = Some(hash);
-                break;
+        // The player reveals the secret committed at battle start
+        let secret = secret.ok_or(RpgError::InvalidSecretReveal)?;
+        verify_player_secret(&battle.secret_hash, &secret)?;
+
+        let randomness = reveal_randomness(
+            battle.randomness_provider,
+            battle.oracle(ctx.accounts.oracle_request.as_ref().map(|request| request.as_ref())),
+            &battle.key(),
+            battle.commit_slot,
+            clock.slot,
+            &ctx.accounts.slot_hashes,
+        )?;
+        battle.rng_seed = mix_player_secret(&randomness, &secret);
+
+        // The battle can't be cancelled from here on, its fee is released
+        match battle.payment_mint {
+            Some(mint) => ctx
+                .accounts
+                .treasury
+                .release_token_entry(&mint, battle.entry_cost, RevenueType::Battle)?,
+            None => {
+                let referral = referral_for(&mut ctx.accounts.referrer, battle.referral_reward)?;
+                ctx.accounts
+                    .treasury
+                    .release_entry(battle.entry_cost, RevenueType::Battle, referral)
             }
-            pos += 32;
         }
-
-        let random_seed = found_hash.ok_or(RpgError::SlotNotFound)?;
-        
-        // Set the battle's RNG seed from the slot hash
-        battle.rng_seed.copy_from_slice(random_seed);
     }
 
-    // Get hero and enemy attributes
-    let hero_attrs = hero.calculate_total_attributes();
-    let enemy_attrs = enemy_template.base_attributes;
-
-    // Determine turn order (higher agility goes first)
-    let hero_goes_first = calculate_battle_priority(hero_attrs.agility, enemy_attrs.agility);
-
-    let mut hero_damage = 0u32;
-    let mut enemy_damage = 0u32;
-
-    if hero_goes_first {
-        // Hero acts first
-        if let Some(damage) = execute_hero_action(action, &hero_attrs, &enemy_attrs, battle)? {
-            hero_damage = damage;
-            battle.damage_enemy(damage);
-        }
-
-        // Enemy acts if still alive
-        if battle.is_enemy_alive() {
-            enemy_damage = execute_enemy_action(enemy_template, &enemy_attrs, &hero_attrs, battle)?;
-            battle.damage_hero(enemy_damage);
-        }
-    } else {
-        // Enemy acts first
-        enemy_damage = execute_enemy_action(enemy_template, &enemy_attrs, &hero_attrs, battle)?;
-        battle.damage_hero(enemy_damage);
-
-        // Hero acts if still alive
-        if battle.is_hero_alive() {
-            if let Some(damage) = execute_hero_action(action, &hero_attrs, &enemy_attrs, battle)? {
-                hero_damage = damage;
-                battle.damage_enemy(damage);
-            }
-        }
+    // Per-turn mode resolves the committed action with a slot hash unknown at commit time
+    if battle.per_turn_randomness {
+        let committed_action = battle.committed_action.ok_or(RpgError::ActionNotCommitted)?;
+        require!(committed_action == action, RpgError::ActionCommitMismatch);
+        validate_randomness_delay(clock.slot, battle.action_commit_slot)?;
+
+        battle.turn_entropy = slot_hash_for(
+            &ctx.accounts.slot_hashes,
+            battle.action_commit_slot + MIN_RANDOMNESS_DELAY_SLOTS,
+        )?;
+        battle.committed_action = None;
     }
 
-    // Increment turn counter
-    battle.next_turn();
+    // Resolve the turn against the attribute snapshots taken at battle start
+    let result = resolve_turn(battle, action)?;
 
     emit!(BattleTurnExecuted {
         battle: battle.key(),
         turn: battle.turn,
         hero_action: action,
-        hero_damage,
-        enemy_damage,
+        hero_damage: result.hero_damage,
+        enemy_damage: result.enemy_damage,
         hero_hp: battle.hero_hp,
         enemy_hp: battle.enemy_hp,
         battle_state: battle.state,
+        log_hash: battle.log_hash,
+        turn_entropy: battle.turn_entropy,
+        actions: result.sequence,
     });
 
     Ok(())
 }
 
-fn execute_hero_action(
-    action: u8,
-    hero_attrs: &Attributes,
-    enemy_attrs: &Attributes,
-    battle: &mut Battle,
-) -> Result<Option<u32>> {
-    match action {
-        ACTION_ATTACK => {
-            let damage = calculate_damage(hero_attrs.strength, enemy_attrs.vitality);
-            
-            // Check for critical hit
-            let crit_chance = (hero_attrs.precision as u32) * 100; // Convert to basis points
-            if rng_check(&battle.rng_seed, battle.turn, crit_chance as u16) {
-                let crit_damage = calculate_critical_damage(damage);
-                Ok(Some(crit_damage))
-            } else {
-                Ok(Some(damage))
-            }
-        }
-        ACTION_DEFEND => {
-            // Defending reduces incoming damage by 50% this turn
-            // This is handled in the enemy attack calculation
-            Ok(None)
-        }
-        ACTION_SKILL => {
-            // Magic attack using intelligence
-            let damage = calculate_damage(hero_attrs.intelligence, enemy_attrs.wisdom);
-            Ok(Some(damage))
-        }
-        ACTION_ESCAPE => {
-            let escape_chance = calculate_escape_chance(hero_attrs.agility, enemy_attrs.agility);
-            if rng_check(&battle.rng_seed, battle.turn, escape_chance) {
-                battle.escape_battle();
-            }
-            Ok(None)
-        }
-        _ => Err(RpgError::InvalidBattleAction.into()),
-    }
-}
-
-fn execute_enemy_action(
-    enemy_template: &EnemyTemplate,
-    enemy_attrs: &Attributes,
-    hero_attrs: &Attributes,
-    battle: &mut Battle,
-) -> Result<u32> {
-    // Simple AI: aggressive enemies always attack, defensive enemies defend if low HP
-    let damage = if enemy_template.is_aggressive() {
-        calculate_damage(enemy_attrs.strength, hero_attrs.vitality)
-    } else if enemy_template.is_defensive() && battle.enemy_hp < battle.enemy_max_hp / 3 {
-        // Defend when low on HP
-        calculate_damage(enemy_attrs.strength, hero_attrs.vitality) / 2
-    } else {
-        // Balanced or normal attack
-        calculate_damage(enemy_attrs.strength, hero_attrs.vitality)
-    };
-
-    Ok(damage)
-}
-
 #[event]
 pub struct BattleTurnExecuted {
     pub battle: Pubkey,
@@ -222,4 +150,7 @@ pub struct BattleTurnExecuted {
     pub hero_hp: u32,
     pub enemy_hp: u32,
     pub battle_state: u8,
+    pub log_hash: [u8; 32],
+    pub turn_entropy: [u8; 32], // Needed to replay per-turn battles
+    pub actions: Vec<CombatAction>,
 }
\ No newline at end of file
@@ -1,12 +1,15 @@
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
+use anchor_spl::token::{Token, TokenAccount};
 use crate::constants::*;
 use crate::errors::RpgError;
 use crate::state::*;
+use crate::utils::math::calculate_referral_reward;
 use crate::utils::validation::*;
+use crate::utils::vault::TokenPaymentAccounts;
 
 #[derive(Accounts)]
-#[instruction(hero_index: u8)]
+#[instruction(hero_index: u8, hero_class: u8)]
 pub struct BuyHero<'info> {
     #[account(mut)]
     pub player_authority: Signer<'info>,
@@ -16,7 +19,7 @@ pub struct BuyHero<'info> {
         seeds = [PLAYER_SEED, player_authority.key().as_ref()],
         bump = player.bump,
         constraint = player.authority == player_authority.key() @ RpgError::Unauthorized,
-        constraint = player.can_add_hero() @ RpgError::MaxHeroesReached
+        constraint = player.can_add_hero(&game_config.economy) @ RpgError::MaxHeroesReached
     )]
     pub player: Account<'info, Player>,
 
@@ -29,6 +32,12 @@ pub struct BuyHero<'info> {
     )]
     pub hero: Account<'info, Hero>,
 
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
     #[account(
         mut,
         seeds = [TREASURY_SEED],
@@ -36,20 +45,47 @@ pub struct BuyHero<'info> {
     )]
     pub treasury: Account<'info, Treasury>,
 
+    #[account(
+        mut,
+        seeds = [VAULT_SEED],
+        bump = treasury.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    /// Player's token account, pass with the treasury token account to pay in the configured mint
+    #[account(
+        mut,
+        constraint = player_token_account.owner == player_authority.key() @ RpgError::InvalidPaymentAccount
+    )]
+    pub player_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_TOKEN_SEED, treasury_token_account.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// Referrer of the player, required when the player has one
+    #[account(
+        mut,
+        constraint = Some(referrer.key()) == player.referrer @ RpgError::InvalidReferrer
+    )]
+    pub referrer: Option<Account<'info, Player>>,
+
     pub system_program: Program<'info, System>,
 }
 
-pub fn buy_hero(ctx: Context<BuyHero>, hero_index: u8) -> Result<()> {
+pub fn buy_hero(ctx: Context<BuyHero>, hero_index: u8, hero_class: u8) -> Result<()> {
     let clock = Clock::get()?;
     let player = &mut ctx.accounts.player;
     let hero = &mut ctx.accounts.hero;
     let treasury = &mut ctx.accounts.treasury;
 
-    // Validate sufficient funds
-    validate_sufficient_funds(
-        ctx.accounts.player_authority.lamports(),
-        HERO_PURCHASE_COST,
-    )?;
+    // Validate hero class
+    validate_hero_class(hero_class)?;
 
     // Validate hero index matches player's next hero
     require!(
@@ -57,45 +93,96 @@ pub fn buy_hero(ctx: Context<BuyHero>, hero_index: u8) -> Result<()> {
         RpgError::HeroIndexOutOfBounds
     );
 
-    // Transfer SOL to treasury
-    system_program::transfer(
-        CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            system_program::Transfer {
-                from: ctx.accounts.player_authority.to_account_info(),
-                to: treasury.to_account_info(),
-            },
-        ),
-        HERO_PURCHASE_COST,
+    // Referrers are owed their share, so a referred player must pass theirs
+    require!(
+        player.referrer.is_none() || ctx.accounts.referrer.is_some(),
+        RpgError::InvalidReferrer
+    );
+
+    // Pay in the configured token when token accounts are passed, SOL otherwise
+    let token_accounts = TokenPaymentAccounts::from_optional(
+        &ctx.accounts.player_token_account,
+        &ctx.accounts.treasury_token_account,
+        &ctx.accounts.token_program,
     )?;
+    let (cost, payment_mint) = match token_accounts {
+        Some(token_accounts) => {
+            let token_payment = ctx
+                .accounts
+                .game_config
+                .token_payment
+                .ok_or(RpgError::TokenPaymentDisabled)?;
+            let cost = token_payment.hero_purchase_cost;
+            token_accounts.collect(&ctx.accounts.player_authority, &token_payment.mint, cost)?;
+            treasury.add_token_revenue(&token_payment.mint, cost, RevenueType::Hero)?;
+            (cost, Some(token_payment.mint))
+        }
+        None => {
+            let cost = ctx.accounts.game_config.economy.hero_purchase_cost;
+
+            // Validate sufficient funds
+            validate_sufficient_funds(
+                ctx.accounts.player_authority.lamports(),
+                cost,
+            )?;
 
-    // Initialize hero with base attributes
+            // Transfer SOL to the treasury vault
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.player_authority.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                    },
+                ),
+                cost,
+            )?;
+
+            // Referrers earn a share of SOL spending off the top of the fee
+            let referral_reward = calculate_referral_reward(&ctx.accounts.game_config.economy, cost);
+            let referral = ctx
+                .accounts
+                .referrer
+                .as_deref_mut()
+                .map(|referrer| (referrer, referral_reward));
+            treasury.add_revenue(cost, RevenueType::Hero, referral);
+            player.add_sol_spent(cost);
+            (cost, None)
+        }
+    };
+
+    // Initialize hero with the class's starting attributes
     hero.owner = ctx.accounts.player_authority.key();
     hero.player = player.key();
     hero.index = hero_index;
+    hero.hero_class = hero_class;
     hero.level = 1;
     hero.xp = 0;
-    hero.base_attributes = Attributes::new_base();
+    hero.base_attributes = Attributes::new_for_class(hero_class);
+    hero.elemental = ElementalProfile {
+        element: CLASS_SKILL_ELEMENTS[hero_class as usize],
+        ..ElementalProfile::default()
+    };
     hero.equipped_weapon = None;
     hero.equipped_armor = None;
     hero.equipped_accessory = None;
     hero.total_battles = 0;
     hero.total_victories = 0;
+    hero.roll_pity = 0;
     hero.created_at = clock.unix_timestamp;
     hero.bump = ctx.bumps.hero;
+    hero.item_count = 0;
 
     // Update player stats
     player.add_hero();
-    player.add_sol_spent(HERO_PURCHASE_COST);
-
-    // Update treasury stats
-    treasury.add_revenue(HERO_PURCHASE_COST, crate::state::treasury::RevenueType::Hero);
 
     emit!(HeroPurchased {
         player: player.key(),
         hero: hero.key(),
         hero_index,
-        cost: HERO_PURCHASE_COST,
+        hero_class,
+        cost,
+        payment_mint,
         timestamp: clock.unix_timestamp,
     });
 
@@ -107,6 +194,8 @@ pub struct HeroPurchased {
     pub player: Pubkey,
     pub hero: Pubkey,
     pub hero_index: u8,
+    pub hero_class: u8,
     pub cost: u64,
+    pub payment_mint: Option<Pubkey>, // None when paid in SOL
     pub timestamp: i64,
 }
\ No newline at end of file
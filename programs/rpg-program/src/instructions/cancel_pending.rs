@@ -1,8 +1,11 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
 use crate::constants::*;
 use crate::errors::RpgError;
 use crate::state::*;
+use crate::utils::randomness::randomness_never_existed;
 use crate::utils::validation::*;
+use crate::utils::vault::{transfer_from_vault, TokenPaymentAccounts};
 
 #[derive(Accounts)]
 pub struct CancelPendingRoll<'info> {
@@ -36,21 +39,117 @@ pub struct CancelPendingRoll<'info> {
         constraint = roll_session.is_pending() @ RpgError::RollAlreadyCompleted
     )]
     pub roll_session: Account<'info, RollSession>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED],
+        bump = treasury.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    /// Player's token account, pass with the treasury token account for refunds of token payments
+    #[account(
+        mut,
+        constraint = player_token_account.owner == player_authority.key() @ RpgError::InvalidPaymentAccount
+    )]
+    pub player_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_TOKEN_SEED, treasury_token_account.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// CHECK: Checked manually for slot hashes sysvar
+    pub slot_hashes: AccountInfo<'info>,
+
+    /// CHECK: Owner and requester checked in utils::randomness, only used by the oracle provider
+    pub oracle_request: Option<UncheckedAccount<'info>>,
+
+    /// Referrer of the player, required when a kept entry earned them a reward
+    #[account(
+        mut,
+        constraint = Some(referrer.key()) == player.referrer @ RpgError::InvalidReferrer
+    )]
+    pub referrer: Option<Account<'info, Player>>,
+
+    pub system_program: Program<'info, System>,
 }
 
 pub fn cancel_pending_roll(ctx: Context<CancelPendingRoll>) -> Result<()> {
     let clock = Clock::get()?;
     let roll_session = &ctx.accounts.roll_session;
+    let player = &mut ctx.accounts.player;
+    let treasury = &mut ctx.accounts.treasury;
 
     // Validate cancel timeout has passed
     validate_cancel_timeout(clock.slot, roll_session.commit_slot)?;
 
+    // Refund the roll cost only if its randomness never existed, otherwise the player may
+    // already know the outcome and cancelling would be a free reroll
+    let amount = roll_session.cost;
+    let refunded = randomness_never_existed(
+        roll_session.randomness_provider,
+        roll_session.oracle(ctx.accounts.oracle_request.as_ref().map(|request| request.as_ref())),
+        &roll_session.key(),
+        roll_session.commit_slot,
+        &ctx.accounts.slot_hashes,
+    )?;
+
+    if refunded {
+        // Reverse the stats recorded at roll start
+        let token_accounts = TokenPaymentAccounts::from_optional(
+            &ctx.accounts.player_token_account,
+            &ctx.accounts.treasury_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        refund_entry(
+            &ctx.accounts.player_authority,
+            player,
+            treasury,
+            &ctx.accounts.vault,
+            &ctx.accounts.system_program,
+            token_accounts,
+            roll_session.payment_mint,
+            amount,
+        )?;
+
+        emit!(EntryRefunded {
+            player: player.key(),
+            hero: ctx.accounts.hero.key(),
+            session: roll_session.key(),
+            amount,
+            payment_mint: roll_session.payment_mint,
+            slot: clock.slot,
+        });
+    } else {
+        keep_entry(
+            treasury,
+            &mut ctx.accounts.referrer,
+            roll_session.payment_mint,
+            amount,
+            roll_session.referral_reward,
+            RevenueType::Roll,
+        )?;
+    }
+
     // Roll session will be closed automatically by the close constraint
-    
+
     emit!(PendingRollCanceled {
         player: ctx.accounts.player.key(),
         hero: ctx.accounts.hero.key(),
         roll_session: roll_session.key(),
+        refunded,
         slot: clock.slot,
     });
 
@@ -88,32 +187,182 @@ pub struct CancelPendingBattle<'info> {
         constraint = battle.turn == 0 @ RpgError::BattleAlreadyCompleted  // Can only cancel before first turn
     )]
     pub battle: Account<'info, Battle>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED],
+        bump = treasury.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    /// Player's token account, pass with the treasury token account for refunds of token payments
+    #[account(
+        mut,
+        constraint = player_token_account.owner == player_authority.key() @ RpgError::InvalidPaymentAccount
+    )]
+    pub player_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_TOKEN_SEED, treasury_token_account.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// CHECK: Checked manually for slot hashes sysvar
+    pub slot_hashes: AccountInfo<'info>,
+
+    /// CHECK: Owner and requester checked in utils::randomness, only used by the oracle provider
+    pub oracle_request: Option<UncheckedAccount<'info>>,
+
+    /// Referrer of the player, required when a kept entry earned them a reward
+    #[account(
+        mut,
+        constraint = Some(referrer.key()) == player.referrer @ RpgError::InvalidReferrer
+    )]
+    pub referrer: Option<Account<'info, Player>>,
+
+    pub system_program: Program<'info, System>,
 }
 
 pub fn cancel_pending_battle(ctx: Context<CancelPendingBattle>) -> Result<()> {
     let clock = Clock::get()?;
     let battle = &ctx.accounts.battle;
+    let player = &mut ctx.accounts.player;
+    let treasury = &mut ctx.accounts.treasury;
 
     // Validate cancel timeout has passed
     validate_cancel_timeout(clock.slot, battle.commit_slot)?;
 
+    // Refund the entry cost only if the battle's seed never existed, as for rolls
+    let amount = battle.entry_cost;
+    let refunded = randomness_never_existed(
+        battle.randomness_provider,
+        battle.oracle(ctx.accounts.oracle_request.as_ref().map(|request| request.as_ref())),
+        &battle.key(),
+        battle.commit_slot,
+        &ctx.accounts.slot_hashes,
+    )?;
+
+    if refunded {
+        // Reverse the stats recorded at battle start
+        let token_accounts = TokenPaymentAccounts::from_optional(
+            &ctx.accounts.player_token_account,
+            &ctx.accounts.treasury_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        refund_entry(
+            &ctx.accounts.player_authority,
+            player,
+            treasury,
+            &ctx.accounts.vault,
+            &ctx.accounts.system_program,
+            token_accounts,
+            battle.payment_mint,
+            amount,
+        )?;
+
+        emit!(EntryRefunded {
+            player: player.key(),
+            hero: ctx.accounts.hero.key(),
+            session: battle.key(),
+            amount,
+            payment_mint: battle.payment_mint,
+            slot: clock.slot,
+        });
+    } else {
+        keep_entry(
+            treasury,
+            &mut ctx.accounts.referrer,
+            battle.payment_mint,
+            amount,
+            battle.referral_reward,
+            RevenueType::Battle,
+        )?;
+    }
+
     // Battle account will be closed automatically by the close constraint
-    
+
     emit!(PendingBattleCanceled {
         player: ctx.accounts.player.key(),
         hero: ctx.accounts.hero.key(),
         battle: battle.key(),
+        refunded,
         slot: clock.slot,
     });
 
     Ok(())
 }
 
+/// Return an entry cost from escrow in the currency it was paid in
+#[allow(clippy::too_many_arguments)]
+fn refund_entry<'info>(
+    player_authority: &Signer<'info>,
+    player: &mut Player,
+    treasury: &mut Treasury,
+    vault: &SystemAccount<'info>,
+    system_program: &Program<'info, System>,
+    token_accounts: Option<TokenPaymentAccounts<'_, 'info>>,
+    payment_mint: Option<Pubkey>,
+    amount: u64,
+) -> Result<()> {
+    match payment_mint {
+        Some(mint) => {
+            let token_accounts = token_accounts.ok_or(RpgError::InvalidPaymentAccount)?;
+            token_accounts.refund(&vault.to_account_info(), treasury.vault_bump, &mint, amount)?;
+            treasury.refund_token_entry(&mint, amount)
+        }
+        None => {
+            // The fee is still in escrow, everything else in the vault stays owed to others
+            treasury.refund_entry(amount);
+            transfer_from_vault(
+                &vault.to_account_info(),
+                &player_authority.to_account_info(),
+                &system_program.to_account_info(),
+                treasury.vault_bump,
+                treasury.allocated_balance(),
+                amount,
+            )?;
+            player.refund_sol_spent(amount);
+            Ok(())
+        }
+    }
+}
+
+/// Release an entry cost from escrow into revenue, as if the session had resolved
+fn keep_entry(
+    treasury: &mut Treasury,
+    referrer: &mut Option<Account<'_, Player>>,
+    payment_mint: Option<Pubkey>,
+    amount: u64,
+    referral_reward: u64,
+    revenue_type: RevenueType,
+) -> Result<()> {
+    match payment_mint {
+        Some(mint) => treasury.release_token_entry(&mint, amount, revenue_type),
+        None => {
+            let referral = referral_for(referrer, referral_reward)?;
+            treasury.release_entry(amount, revenue_type, referral);
+            Ok(())
+        }
+    }
+}
+
 #[event]
 pub struct PendingRollCanceled {
     pub player: Pubkey,
     pub hero: Pubkey,
     pub roll_session: Pubkey,
+    pub refunded: bool, // False when the entry cost was kept
     pub slot: u64,
 }
 
@@ -122,5 +371,16 @@ pub struct PendingBattleCanceled {
     pub player: Pubkey,
     pub hero: Pubkey,
     pub battle: Pubkey,
+    pub refunded: bool, // False when the entry cost was kept
+    pub slot: u64,
+}
+
+#[event]
+pub struct EntryRefunded {
+    pub player: Pubkey,
+    pub hero: Pubkey,
+    pub session: Pubkey, // Roll session or battle
+    pub amount: u64,
+    pub payment_mint: Option<Pubkey>, // None when refunded in SOL
     pub slot: u64,
-}
\ No newline at end of file
+}
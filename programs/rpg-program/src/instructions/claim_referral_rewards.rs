@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+use crate::utils::vault::transfer_from_vault;
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(mut)]
+    pub player_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_SEED, player_authority.key().as_ref()],
+        bump = player.bump,
+        constraint = player.authority == player_authority.key() @ RpgError::Unauthorized
+    )]
+    pub player: Account<'info, Player>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED],
+        bump = treasury.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Pay out the rewards a referrer earned from their referees' spending
+pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+    let clock = Clock::get()?;
+    let player = &mut ctx.accounts.player;
+    let treasury = &mut ctx.accounts.treasury;
+
+    let amount = player.claim_referral_balance()?;
+    treasury.pay_referral(amount);
+
+    // Rewards were set aside when earned, everything else allocated stays in the vault
+    transfer_from_vault(
+        &ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.player_authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        treasury.vault_bump,
+        treasury.allocated_balance(),
+        amount,
+    )?;
+
+    emit!(ReferralRewardsClaimed {
+        player: player.key(),
+        amount,
+        total_referral_earned: player.total_referral_earned,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct ReferralRewardsClaimed {
+    pub player: Pubkey,
+    pub amount: u64,
+    pub total_referral_earned: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct CloseBattle<'info> {
+    /// Anyone may close an old settled battle; the hero owner may close it right away
+    pub caller: Signer<'info>,
+
+    /// CHECK: Rent receiver, must be the hero owner who paid for the battle
+    #[account(
+        mut,
+        address = hero.owner @ RpgError::Unauthorized
+    )]
+    pub player_authority: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [HERO_SEED, hero.player.as_ref(), &hero.index.to_le_bytes()],
+        bump = hero.bump
+    )]
+    pub hero: Account<'info, Hero>,
+
+    #[account(
+        mut,
+        close = player_authority,
+        seeds = [BATTLE_SEED, hero.key().as_ref(), &battle.nonce.to_le_bytes()],
+        bump = battle.bump,
+        constraint = battle.hero == hero.key() @ RpgError::BattleNotFound,
+        constraint = battle.settled @ RpgError::BattleNotSettled
+    )]
+    pub battle: Account<'info, Battle>,
+}
+
+pub fn close_battle(ctx: Context<CloseBattle>) -> Result<()> {
+    let clock = Clock::get()?;
+    let battle = &ctx.accounts.battle;
+
+    // Third parties have to wait for the cleanup delay
+    if ctx.accounts.caller.key() != ctx.accounts.hero.owner {
+        require!(
+            battle.can_be_cleaned_up(clock.unix_timestamp),
+            RpgError::BattleCleanupNotExpired
+        );
+    }
+
+    // Battle account will be closed automatically by the close constraint
+
+    emit!(BattleClosed {
+        hero: ctx.accounts.hero.key(),
+        battle: battle.key(),
+        closed_by: ctx.accounts.caller.key(),
+        rent_receiver: ctx.accounts.player_authority.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct BattleClosed {
+    pub hero: Pubkey,
+    pub battle: Pubkey,
+    pub closed_by: Pubkey,
+    pub rent_receiver: Pubkey,
+    pub timestamp: i64,
+}
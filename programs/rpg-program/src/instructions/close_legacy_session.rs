@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct CloseLegacyBattle<'info> {
+    #[account(mut)]
+    pub player_authority: Signer<'info>,
+
+    /// The hero has to be migrated first, see `migrate_hero`
+    #[account(
+        seeds = [HERO_SEED, hero.player.as_ref(), &hero.index.to_le_bytes()],
+        bump = hero.bump,
+        constraint = hero.owner == player_authority.key() @ RpgError::Unauthorized
+    )]
+    pub hero: Account<'info, Hero>,
+
+    /// CHECK: Still in the legacy layout, which `Account<Battle>` can't read; parsed by `LegacyBattle::from_account_data`
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
+    pub battle: UncheckedAccount<'info>,
+}
+
+/// Close a battle started before the current layout and refund its rent to the hero owner
+pub fn close_legacy_battle(ctx: Context<CloseLegacyBattle>) -> Result<()> {
+    let clock = Clock::get()?;
+    let battle = &ctx.accounts.battle;
+
+    let legacy = LegacyBattle::from_account_data(&battle.try_borrow_data()?)?;
+    require!(legacy.hero == ctx.accounts.hero.key(), RpgError::BattleNotFound);
+
+    // Legacy battles never escrowed their entry, so only the rent comes back
+    close_account(battle, &ctx.accounts.player_authority)?;
+
+    emit!(LegacySessionClosed {
+        hero: ctx.accounts.hero.key(),
+        session: battle.key(),
+        nonce: legacy.nonce,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseLegacyRollSession<'info> {
+    #[account(mut)]
+    pub player_authority: Signer<'info>,
+
+    /// The hero has to be migrated first, see `migrate_hero`
+    #[account(
+        seeds = [HERO_SEED, hero.player.as_ref(), &hero.index.to_le_bytes()],
+        bump = hero.bump,
+        constraint = hero.owner == player_authority.key() @ RpgError::Unauthorized
+    )]
+    pub hero: Account<'info, Hero>,
+
+    /// CHECK: Still in the legacy layout, which `Account<RollSession>` can't read; parsed by `LegacyRollSession::from_account_data`
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
+    pub roll_session: UncheckedAccount<'info>,
+}
+
+/// Close a roll session started before the current layout and refund its rent to the hero owner
+pub fn close_legacy_roll_session(ctx: Context<CloseLegacyRollSession>) -> Result<()> {
+    let clock = Clock::get()?;
+    let roll_session = &ctx.accounts.roll_session;
+
+    let legacy = LegacyRollSession::from_account_data(&roll_session.try_borrow_data()?)?;
+    require!(
+        legacy.hero == ctx.accounts.hero.key() && legacy.player == ctx.accounts.hero.player,
+        RpgError::RollSessionNotFound
+    );
+
+    // Legacy sessions never escrowed their cost, so only the rent comes back
+    close_account(roll_session, &ctx.accounts.player_authority)?;
+
+    emit!(LegacySessionClosed {
+        hero: ctx.accounts.hero.key(),
+        session: roll_session.key(),
+        nonce: legacy.nonce,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Move the account's lamports to the receiver and hand it back to the system program
+fn close_account<'info>(account: &UncheckedAccount<'info>, receiver: &Signer<'info>) -> Result<()> {
+    let lamports = account.lamports();
+    account.sub_lamports(lamports)?;
+    receiver.add_lamports(lamports)?;
+    account.assign(&anchor_lang::system_program::ID);
+    account.resize(0)?;
+    Ok(())
+}
+
+#[event]
+pub struct LegacySessionClosed {
+    pub hero: Pubkey,
+    pub session: Pubkey,
+    pub nonce: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+use crate::utils::validation::*;
+
+#[derive(Accounts)]
+#[instruction(id: u32)]
+pub struct CreateBanner<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump,
+        constraint = game_config.authority == authority.key() @ RpgError::Unauthorized
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(
+        seeds = [ODDS_TABLE_SEED, &odds_table.id.to_le_bytes()],
+        bump = odds_table.bump
+    )]
+    pub odds_table: Account<'info, OddsTable>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Banner::LEN,
+        seeds = [BANNER_SEED, &id.to_le_bytes()],
+        bump
+    )]
+    pub banner: Account<'info, Banner>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_banner(
+    ctx: Context<CreateBanner>,
+    id: u32,
+    name: [u8; 32],
+    start_time: i64,
+    end_time: i64,
+    roll_cost: u64,
+    roll_batch_cost: u64,
+    featured_min_rarity: u8,
+    featured_pool: Vec<LootEntry>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let banner = &mut ctx.accounts.banner;
+
+    // Validate time window and featured rewards
+    validate_banner(start_time, end_time, featured_min_rarity, &featured_pool)?;
+
+    banner.id = id;
+    banner.name = name;
+    banner.start_time = start_time;
+    banner.end_time = end_time;
+    banner.roll_cost = roll_cost;
+    banner.roll_batch_cost = roll_batch_cost;
+    banner.odds_table = ctx.accounts.odds_table.key();
+    banner.featured_min_rarity = featured_min_rarity;
+    banner.set_featured(&featured_pool);
+    banner.created_at = clock.unix_timestamp;
+    banner.bump = ctx.bumps.banner;
+
+    emit!(BannerCreated {
+        banner: banner.key(),
+        id,
+        odds_table: banner.odds_table,
+        start_time,
+        end_time,
+        roll_cost,
+        roll_batch_cost,
+        featured_count: banner.featured_count,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct BannerCreated {
+    pub banner: Pubkey,
+    pub id: u32,
+    pub odds_table: Pubkey,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub roll_cost: u64,
+    pub roll_batch_cost: u64,
+    pub featured_count: u8,
+}
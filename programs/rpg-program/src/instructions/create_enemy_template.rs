@@ -1,6 +1,5 @@
 use anchor_lang::prelude::*;
 use crate::constants::*;
-use crate::errors::RpgError;
 use crate::state::*;
 use crate::utils::validation::*;
 
@@ -19,9 +18,16 @@ pub struct CreateEnemyTemplate<'info> {
     )]
     pub enemy_template: Account<'info, EnemyTemplate>,
 
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
     pub system_program: Program<'info, System>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_enemy_template(
     ctx: Context<CreateEnemyTemplate>,
     id: u32,
@@ -37,12 +43,17 @@ pub fn create_enemy_template(
     luck: u16,
     ai_flags: u8,
     xp_reward: u32,
+    element: u8,
+    resistances: [i16; ELEMENT_COUNT],
 ) -> Result<()> {
     let clock = Clock::get()?;
     let enemy_template = &mut ctx.accounts.enemy_template;
 
     // Validate enemy template data
-    validate_enemy_template(level, &name, ai_flags)?;
+    validate_enemy_template(level, &name, ai_flags, ctx.accounts.game_config.economy.max_level)?;
+
+    let elemental = ElementalProfile { element, resistances };
+    validate_elemental_profile(&elemental)?;
 
     enemy_template.id = id;
     enemy_template.name = name;
@@ -57,6 +68,7 @@ pub fn create_enemy_template(
         precision,
         luck,
     };
+    enemy_template.elemental = elemental;
     enemy_template.ai_flags = ai_flags;
     enemy_template.xp_reward = xp_reward;
     enemy_template.created_at = clock.unix_timestamp;
@@ -68,6 +80,7 @@ pub fn create_enemy_template(
         name: enemy_template.get_name_string(),
         level,
         xp_reward,
+        element,
         timestamp: clock.unix_timestamp,
     });
 
@@ -81,5 +94,6 @@ pub struct EnemyTemplateCreated {
     pub name: String,
     pub level: u16,
     pub xp_reward: u32,
+    pub element: u8,
     pub timestamp: i64,
 }
\ No newline at end of file
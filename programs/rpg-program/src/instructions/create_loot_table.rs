@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+use crate::utils::validation::*;
+
+#[derive(Accounts)]
+pub struct CreateLootTable<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump,
+        constraint = game_config.authority == authority.key() @ RpgError::Unauthorized
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(
+        seeds = [ENEMY_TEMPLATE_SEED, &enemy_template.id.to_le_bytes()],
+        bump = enemy_template.bump
+    )]
+    pub enemy_template: Account<'info, EnemyTemplate>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = LootTable::LEN,
+        seeds = [LOOT_TABLE_SEED, enemy_template.key().as_ref()],
+        bump
+    )]
+    pub loot_table: Account<'info, LootTable>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_loot_table(ctx: Context<CreateLootTable>, entries: Vec<LootEntry>) -> Result<()> {
+    let clock = Clock::get()?;
+    let loot_table = &mut ctx.accounts.loot_table;
+
+    // Validate loot entries
+    validate_loot_entries(&entries)?;
+
+    loot_table.enemy_template = ctx.accounts.enemy_template.key();
+    loot_table.set_entries(&entries);
+    loot_table.created_at = clock.unix_timestamp;
+    loot_table.bump = ctx.bumps.loot_table;
+
+    emit!(LootTableCreated {
+        loot_table: loot_table.key(),
+        enemy_template: loot_table.enemy_template,
+        entry_count: loot_table.entry_count,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct LootTableCreated {
+    pub loot_table: Pubkey,
+    pub enemy_template: Pubkey,
+    pub entry_count: u8,
+    pub timestamp: i64,
+}
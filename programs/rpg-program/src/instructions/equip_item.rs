@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct EquipItem<'info> {
+    pub player_authority: Signer<'info>,
+
+    #[account(
+        seeds = [PLAYER_SEED, player_authority.key().as_ref()],
+        bump = player.bump,
+        constraint = player.authority == player_authority.key() @ RpgError::Unauthorized
+    )]
+    pub player: Account<'info, Player>,
+
+    #[account(
+        mut,
+        seeds = [HERO_SEED, player.key().as_ref(), &hero.index.to_le_bytes()],
+        bump = hero.bump,
+        constraint = hero.owner == player_authority.key() @ RpgError::Unauthorized,
+        constraint = hero.player == player.key() @ RpgError::HeroNotFound
+    )]
+    pub hero: Account<'info, Hero>,
+
+    #[account(
+        constraint = item.hero == hero.key() @ RpgError::ItemNotFound
+    )]
+    pub item: Account<'info, Item>,
+}
+
+/// Put an item in its slot, replacing whatever was equipped there
+pub fn equip_item(ctx: Context<EquipItem>) -> Result<()> {
+    let hero = &mut ctx.accounts.hero;
+    let item = &ctx.accounts.item;
+
+    hero.equip_item(item.item_type, item.key())?;
+
+    emit!(ItemEquipped {
+        hero: hero.key(),
+        item: item.key(),
+        item_type: item.item_type,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct ItemEquipped {
+    pub hero: Pubkey,
+    pub item: Pubkey,
+    pub item_type: u8,
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct ForfeitBattle<'info> {
+    pub player_authority: Signer<'info>,
+
+    #[account(
+        seeds = [PLAYER_SEED, player_authority.key().as_ref()],
+        bump = player.bump,
+        constraint = player.authority == player_authority.key() @ RpgError::Unauthorized
+    )]
+    pub player: Account<'info, Player>,
+
+    #[account(
+        seeds = [HERO_SEED, player.key().as_ref(), &hero.index.to_le_bytes()],
+        bump = hero.bump,
+        constraint = hero.owner == player_authority.key() @ RpgError::Unauthorized,
+        constraint = hero.player == player.key() @ RpgError::HeroNotFound
+    )]
+    pub hero: Account<'info, Hero>,
+
+    #[account(
+        mut,
+        seeds = [BATTLE_SEED, hero.key().as_ref(), &battle.nonce.to_le_bytes()],
+        bump = battle.bump,
+        constraint = battle.hero == hero.key() @ RpgError::BattleNotFound,
+        constraint = battle.is_active() @ RpgError::BattleAlreadyCompleted,
+        constraint = battle.turn > 0 @ RpgError::InvalidBattleAction  // Unstarted battles are cancelled instead
+    )]
+    pub battle: Account<'info, Battle>,
+}
+
+pub fn forfeit_battle(ctx: Context<ForfeitBattle>) -> Result<()> {
+    let clock = Clock::get()?;
+    let battle = &mut ctx.accounts.battle;
+
+    // Always available, so a turn whose reveal slot is gone can't lock the battle.
+    // The entry cost is kept and the battle settles as a loss.
+    battle.forfeit();
+
+    emit!(BattleForfeited {
+        hero: ctx.accounts.hero.key(),
+        battle: battle.key(),
+        turn: battle.turn,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct BattleForfeited {
+    pub hero: Pubkey,
+    pub battle: Pubkey,
+    pub turn: u8,
+    pub timestamp: i64,
+}
@@ -0,0 +1,141 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+use crate::utils::validation::*;
+
+#[derive(Accounts)]
+pub struct InitializeGameConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        constraint = treasury.authority == authority.key() @ RpgError::Unauthorized
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = GameConfig::LEN,
+        seeds = [GAME_CONFIG_SEED],
+        bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_game_config(ctx: Context<InitializeGameConfig>) -> Result<()> {
+    let clock = Clock::get()?;
+    let game_config = &mut ctx.accounts.game_config;
+
+    // Start from the compile-time defaults
+    game_config.authority = ctx.accounts.authority.key();
+    game_config.combat = CombatConfig::default();
+    game_config.economy = EconomyConfig::default();
+    game_config.randomness_provider = RANDOMNESS_PROVIDER_SLOT_HASHES;
+    game_config.oracle_program = Pubkey::default();
+    game_config.oracle_layout = OracleLayout::default();
+    game_config.token_payment = None;
+    game_config.updated_at = clock.unix_timestamp;
+    game_config.bump = ctx.bumps.game_config;
+
+    emit!(GameConfigUpdated {
+        game_config: game_config.key(),
+        authority: game_config.authority,
+        combat: game_config.combat,
+        economy: game_config.economy,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateGameConfig<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump,
+        constraint = game_config.authority == authority.key() @ RpgError::Unauthorized
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+pub fn update_game_config(
+    ctx: Context<UpdateGameConfig>,
+    combat: CombatConfig,
+    economy: EconomyConfig,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let game_config = &mut ctx.accounts.game_config;
+
+    // Validate new tuning values
+    validate_game_config(&combat, &economy)?;
+
+    // Running battles keep the combat config they were started with
+    game_config.combat = combat;
+    game_config.economy = economy;
+    game_config.updated_at = clock.unix_timestamp;
+
+    emit!(GameConfigUpdated {
+        game_config: game_config.key(),
+        authority: game_config.authority,
+        combat,
+        economy,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_randomness_provider(
+    ctx: Context<UpdateGameConfig>,
+    provider: u8,
+    oracle_program: Pubkey,
+    oracle_layout: OracleLayout,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let game_config = &mut ctx.accounts.game_config;
+
+    validate_randomness_provider(provider, &oracle_program, &oracle_layout)?;
+
+    // Pending rolls and battles keep the provider they were started with
+    game_config.randomness_provider = provider;
+    game_config.oracle_program = oracle_program;
+    game_config.oracle_layout = oracle_layout;
+    game_config.updated_at = clock.unix_timestamp;
+
+    emit!(RandomnessProviderChanged {
+        game_config: game_config.key(),
+        provider,
+        oracle_program,
+        oracle_layout,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct GameConfigUpdated {
+    pub game_config: Pubkey,
+    pub authority: Pubkey,
+    pub combat: CombatConfig,
+    pub economy: EconomyConfig,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RandomnessProviderChanged {
+    pub game_config: Pubkey,
+    pub provider: u8,
+    pub oracle_program: Pubkey,
+    pub oracle_layout: OracleLayout,
+    pub timestamp: i64,
+}
@@ -16,6 +16,12 @@ pub struct InitializeTreasury<'info> {
     )]
     pub treasury: Account<'info, Treasury>,
 
+    #[account(
+        seeds = [VAULT_SEED],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -28,6 +34,17 @@ pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
     treasury.total_battles = 0;
     treasury.total_rolls = 0;
     treasury.total_heroes_created = 0;
+    treasury.total_withdrawn = 0;
+    treasury.reserve = TREASURY_RESERVE;
+    treasury.vault_bump = ctx.bumps.vault;
+    treasury.token_revenue = [MintRevenue::default(); MAX_PAYMENT_MINTS];
+    // Everything goes to the authority's dev bucket until a split is configured
+    treasury.revenue_shares = [REVENUE_SHARE_TOTAL, 0, 0, 0];
+    treasury.bucket_recipients = [treasury.authority; REVENUE_BUCKET_COUNT];
+    treasury.bucket_balances = [0; REVENUE_BUCKET_COUNT];
+    treasury.bucket_claimed = [0; REVENUE_BUCKET_COUNT];
+    treasury.referral_owed = 0;
+    treasury.pending_escrow = 0;
     treasury.created_at = clock.unix_timestamp;
     treasury.bump = ctx.bumps.treasury;
 
@@ -0,0 +1,173 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+use crate::utils::math::{calculate_repair_cost, calculate_upgrade_cost};
+use crate::utils::validation::*;
+
+#[derive(Accounts)]
+pub struct BuyItem<'info> {
+    #[account(mut)]
+    pub player_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_SEED, player_authority.key().as_ref()],
+        bump = player.bump,
+        constraint = player.authority == player_authority.key() @ RpgError::Unauthorized
+    )]
+    pub player: Account<'info, Player>,
+
+    #[account(
+        mut,
+        seeds = [HERO_SEED, player.key().as_ref(), &hero.index.to_le_bytes()],
+        bump = hero.bump,
+        constraint = hero.owner == player_authority.key() @ RpgError::Unauthorized,
+        constraint = hero.player == player.key() @ RpgError::HeroNotFound
+    )]
+    pub hero: Account<'info, Hero>,
+
+    /// Every item is derived from the hero and its item count, shared with dropped items
+    #[account(
+        init,
+        payer = player_authority,
+        space = Item::LEN,
+        seeds = [ITEM_SEED, hero.key().as_ref(), &hero.item_count.to_le_bytes()],
+        bump
+    )]
+    pub item: Account<'info, Item>,
+
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Buy a common item of `item_type` for gold
+pub fn buy_item(ctx: Context<BuyItem>, item_type: u8) -> Result<()> {
+    let clock = Clock::get()?;
+    let player = &mut ctx.accounts.player;
+    let hero = &mut ctx.accounts.hero;
+    hero.next_item_index();
+
+    let generated = Item::generate(hero.key(), item_type, RARITY_COMMON, ELEMENT_NEUTRAL, clock.unix_timestamp);
+    validate_item_data(item_type, generated.rarity, &generated.name)?;
+
+    let cost = ctx.accounts.game_config.economy.item_shop_cost;
+    player.spend_gold(cost)?;
+
+    let item = &mut ctx.accounts.item;
+    item.set_inner(Item {
+        bump: ctx.bumps.item,
+        ..generated
+    });
+
+    emit!(ItemPurchased {
+        player: player.key(),
+        hero: hero.key(),
+        item: item.key(),
+        item_type,
+        gold_spent: cost,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ImproveItem<'info> {
+    pub player_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_SEED, player_authority.key().as_ref()],
+        bump = player.bump,
+        constraint = player.authority == player_authority.key() @ RpgError::Unauthorized
+    )]
+    pub player: Account<'info, Player>,
+
+    #[account(
+        seeds = [HERO_SEED, player.key().as_ref(), &hero.index.to_le_bytes()],
+        bump = hero.bump,
+        constraint = hero.owner == player_authority.key() @ RpgError::Unauthorized,
+        constraint = hero.player == player.key() @ RpgError::HeroNotFound
+    )]
+    pub hero: Account<'info, Hero>,
+
+    #[account(
+        mut,
+        constraint = item.hero == hero.key() @ RpgError::ItemNotFound
+    )]
+    pub item: Account<'info, Item>,
+
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+/// Restore an item to full durability, paying gold per point restored
+pub fn repair_item(ctx: Context<ImproveItem>) -> Result<()> {
+    let player = &mut ctx.accounts.player;
+    let item = &mut ctx.accounts.item;
+
+    let cost = calculate_repair_cost(&ctx.accounts.game_config.economy, item.durability);
+    item.repair()?;
+    player.spend_gold(cost)?;
+
+    emit!(ItemRepaired {
+        player: player.key(),
+        item: item.key(),
+        durability: item.durability,
+        gold_spent: cost,
+    });
+
+    Ok(())
+}
+
+/// Raise an item to the next rarity for gold
+pub fn upgrade_item(ctx: Context<ImproveItem>) -> Result<()> {
+    let player = &mut ctx.accounts.player;
+    let item = &mut ctx.accounts.item;
+
+    let cost = calculate_upgrade_cost(&ctx.accounts.game_config.economy, item.rarity);
+    let rarity = item.upgrade()?;
+    player.spend_gold(cost)?;
+
+    emit!(ItemUpgraded {
+        player: player.key(),
+        item: item.key(),
+        rarity,
+        gold_spent: cost,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct ItemPurchased {
+    pub player: Pubkey,
+    pub hero: Pubkey,
+    pub item: Pubkey,
+    pub item_type: u8,
+    pub gold_spent: u64,
+}
+
+#[event]
+pub struct ItemRepaired {
+    pub player: Pubkey,
+    pub item: Pubkey,
+    pub durability: u16,
+    pub gold_spent: u64,
+}
+
+#[event]
+pub struct ItemUpgraded {
+    pub player: Pubkey,
+    pub item: Pubkey,
+    pub rarity: u8,
+    pub gold_spent: u64,
+}
@@ -23,11 +23,18 @@ pub struct LevelUpHero<'info> {
         bump = hero.bump,
         constraint = hero.owner == player_authority.key() @ RpgError::Unauthorized,
         constraint = hero.player == player.key() @ RpgError::HeroNotFound,
-        constraint = hero.can_level_up() @ RpgError::InsufficientXp
+        constraint = hero.can_level_up(&game_config.economy) @ RpgError::InsufficientXp
     )]
     pub hero: Account<'info, Hero>,
+
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn level_up_hero(
     ctx: Context<LevelUpHero>,
     strength: u16,
@@ -42,7 +49,7 @@ pub fn level_up_hero(
     let hero = &mut ctx.accounts.hero;
 
     // Level up the hero (this validates XP and deducts it)
-    let available_points = hero.level_up()?;
+    let available_points = hero.level_up(&ctx.accounts.game_config.economy)?;
 
     // Validate attribute distribution
     validate_attribute_distribution(
@@ -67,6 +74,10 @@ pub fn level_up_hero(
     hero.base_attributes.precision = hero.base_attributes.precision.saturating_add(precision);
     hero.base_attributes.luck = hero.base_attributes.luck.saturating_add(luck);
 
+    // Apply the class's free growth bonus
+    let class_bonus = Attributes::class_level_bonus(hero.hero_class);
+    hero.base_attributes.add(&class_bonus);
+
     emit!(HeroLeveledUp {
         player: ctx.accounts.player.key(),
         hero: hero.key(),
@@ -81,6 +92,16 @@ pub fn level_up_hero(
             precision,
             luck,
         },
+        class_bonus: AttributeIncrease {
+            strength: class_bonus.strength,
+            dexterity: class_bonus.dexterity,
+            vitality: class_bonus.vitality,
+            intelligence: class_bonus.intelligence,
+            wisdom: class_bonus.wisdom,
+            agility: class_bonus.agility,
+            precision: class_bonus.precision,
+            luck: class_bonus.luck,
+        },
     });
 
     Ok(())
@@ -92,6 +113,7 @@ pub struct HeroLeveledUp {
     pub hero: Pubkey,
     pub new_level: u16,
     pub attributes_added: AttributeIncrease,
+    pub class_bonus: AttributeIncrease,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
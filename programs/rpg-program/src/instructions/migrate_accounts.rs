@@ -0,0 +1,167 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct MigratePlayer<'info> {
+    #[account(mut)]
+    pub player_authority: Signer<'info>,
+
+    /// CHECK: Still in the legacy layout, which `Account<Player>` can't read; parsed by `Player::from_legacy`
+    #[account(
+        mut,
+        seeds = [PLAYER_SEED, player_authority.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    pub player: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grow a player created before gold and referrals to the current layout
+pub fn migrate_player(ctx: Context<MigratePlayer>) -> Result<()> {
+    let clock = Clock::get()?;
+    let player = &ctx.accounts.player;
+
+    let migrated = Player::from_legacy(&player.try_borrow_data()?)?;
+    require!(
+        migrated.authority == ctx.accounts.player_authority.key(),
+        RpgError::Unauthorized
+    );
+
+    write_migrated(
+        player,
+        &ctx.accounts.player_authority,
+        &ctx.accounts.system_program,
+        &migrated,
+        Player::LEN,
+    )?;
+
+    emit!(AccountMigrated {
+        account: player.key(),
+        migrated_by: ctx.accounts.player_authority.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigrateHero<'info> {
+    #[account(mut)]
+    pub player_authority: Signer<'info>,
+
+    /// CHECK: Still in the legacy layout, which `Account<Hero>` can't read; parsed by `Hero::from_legacy`
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
+    pub hero: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grow a hero created before classes and elements to the current layout as a Warrior
+pub fn migrate_hero(ctx: Context<MigrateHero>) -> Result<()> {
+    let clock = Clock::get()?;
+    let hero = &ctx.accounts.hero;
+
+    let migrated = Hero::from_legacy(&hero.try_borrow_data()?)?;
+    require!(
+        migrated.owner == ctx.accounts.player_authority.key(),
+        RpgError::Unauthorized
+    );
+
+    write_migrated(
+        hero,
+        &ctx.accounts.player_authority,
+        &ctx.accounts.system_program,
+        &migrated,
+        Hero::LEN,
+    )?;
+
+    emit!(AccountMigrated {
+        account: hero.key(),
+        migrated_by: ctx.accounts.player_authority.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigrateEnemyTemplate<'info> {
+    /// Anyone may migrate a template, like anyone may create one
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Still in the legacy layout, which `Account<EnemyTemplate>` can't read; parsed by `EnemyTemplate::from_legacy`
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
+    pub enemy_template: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grow an enemy template created before elements to the current layout as a neutral enemy
+pub fn migrate_enemy_template(ctx: Context<MigrateEnemyTemplate>) -> Result<()> {
+    let clock = Clock::get()?;
+    let enemy_template = &ctx.accounts.enemy_template;
+
+    let migrated = EnemyTemplate::from_legacy(&enemy_template.try_borrow_data()?)?;
+
+    write_migrated(
+        enemy_template,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+        &migrated,
+        EnemyTemplate::LEN,
+    )?;
+
+    emit!(AccountMigrated {
+        account: enemy_template.key(),
+        migrated_by: ctx.accounts.payer.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Resize a legacy account to `len`, with the payer covering any extra rent, and write it back
+fn write_migrated<'info, T: AccountSerialize>(
+    account: &UncheckedAccount<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    migrated: &T,
+    len: usize,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(len);
+    let shortfall = rent.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer {
+                    from: payer.to_account_info(),
+                    to: account.to_account_info(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+
+    account.resize(len)?;
+    migrated.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub migrated_by: Pubkey,
+    pub timestamp: i64,
+}
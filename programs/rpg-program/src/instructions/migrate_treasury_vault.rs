@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct MigrateTreasuryVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Still in the legacy layout, which `Account<Treasury>` can't read; parsed by `Treasury::from_legacy`
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump,
+        owner = crate::ID
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grow a pre-vault treasury to the current layout and move the fees it collected into the vault
+pub fn migrate_treasury_vault(ctx: Context<MigrateTreasuryVault>) -> Result<()> {
+    let clock = Clock::get()?;
+    let treasury = &ctx.accounts.treasury;
+
+    // Everything above the treasury's rent at its new size belongs in the vault
+    let rent = Rent::get()?.minimum_balance(Treasury::LEN);
+    let amount = treasury.lamports().saturating_sub(rent);
+
+    let migrated = Treasury::from_legacy(&treasury.try_borrow_data()?, ctx.bumps.vault, amount)?;
+    require!(
+        migrated.authority == ctx.accounts.authority.key(),
+        RpgError::Unauthorized
+    );
+
+    // The authority covers any rent the larger account needs
+    let shortfall = rent.saturating_sub(treasury.lamports());
+    if shortfall > 0 {
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: treasury.to_account_info(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+
+    treasury.resize(Treasury::LEN)?;
+    migrated.try_serialize(&mut &mut treasury.try_borrow_mut_data()?[..])?;
+
+    if amount > 0 {
+        treasury.sub_lamports(amount)?;
+        ctx.accounts.vault.add_lamports(amount)?;
+    }
+
+    emit!(TreasuryVaultMigrated {
+        treasury: treasury.key(),
+        vault: ctx.accounts.vault.key(),
+        amount,
+        vault_balance: ctx.accounts.vault.lamports(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct TreasuryVaultMigrated {
+    pub treasury: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub vault_balance: u64,
+    pub timestamp: i64,
+}
@@ -1,23 +1,59 @@
 pub mod player_initialize;
 pub mod buy_hero;
 pub mod level_up_hero;
+pub mod set_skill_element;
 pub mod battle_start;
+pub mod battle_commit_action;
 pub mod battle_turn;
 pub mod battle_settle;
+pub mod forfeit_battle;
+pub mod close_battle;
 pub mod roll_start;
 pub mod roll_fulfill;
 pub mod create_enemy_template;
+pub mod create_loot_table;
 pub mod initialize_treasury;
+pub mod withdraw_treasury;
+pub mod migrate_treasury_vault;
+pub mod migrate_accounts;
+pub mod close_legacy_session;
+pub mod token_payment;
+pub mod revenue_split;
+pub mod claim_referral_rewards;
+pub mod game_config;
+pub mod odds_table;
+pub mod create_banner;
 pub mod cancel_pending;
+pub mod recommit;
+pub mod item_shop;
+pub mod equip_item;
 
 pub use player_initialize::*;
 pub use buy_hero::*;
 pub use level_up_hero::*;
+pub use set_skill_element::*;
 pub use battle_start::*;
+pub use battle_commit_action::*;
 pub use battle_turn::*;
 pub use battle_settle::*;
+pub use forfeit_battle::*;
+pub use close_battle::*;
 pub use roll_start::*;
 pub use roll_fulfill::*;
 pub use create_enemy_template::*;
+pub use create_loot_table::*;
 pub use initialize_treasury::*;
-pub use cancel_pending::*;
\ No newline at end of file
+pub use withdraw_treasury::*;
+pub use migrate_treasury_vault::*;
+pub use migrate_accounts::*;
+pub use close_legacy_session::*;
+pub use token_payment::*;
+pub use revenue_split::*;
+pub use claim_referral_rewards::*;
+pub use game_config::*;
+pub use odds_table::*;
+pub use create_banner::*;
+pub use cancel_pending::*;
+pub use recommit::*;
+pub use item_shop::*;
+pub use equip_item::*;
\ No newline at end of file
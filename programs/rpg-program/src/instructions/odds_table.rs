@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+use crate::utils::validation::*;
+
+#[derive(Accounts)]
+#[instruction(id: u32)]
+pub struct CreateOddsTable<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump,
+        constraint = game_config.authority == authority.key() @ RpgError::Unauthorized
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = OddsTable::LEN,
+        seeds = [ODDS_TABLE_SEED, &id.to_le_bytes()],
+        bump
+    )]
+    pub odds_table: Account<'info, OddsTable>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_odds_table(
+    ctx: Context<CreateOddsTable>,
+    id: u32,
+    weights: [u16; RARITY_COUNT],
+    xp_rewards: [u32; RARITY_COUNT],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let odds_table = &mut ctx.accounts.odds_table;
+
+    // Weights must add up to 100%
+    validate_odds_table(&weights)?;
+
+    odds_table.id = id;
+    odds_table.weights = weights;
+    odds_table.xp_rewards = xp_rewards;
+    odds_table.updated_at = clock.unix_timestamp;
+    odds_table.bump = ctx.bumps.odds_table;
+
+    emit!(OddsTableUpdated {
+        odds_table: odds_table.key(),
+        id,
+        weights,
+        xp_rewards,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateOddsTable<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump,
+        constraint = game_config.authority == authority.key() @ RpgError::Unauthorized
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(
+        mut,
+        seeds = [ODDS_TABLE_SEED, &odds_table.id.to_le_bytes()],
+        bump = odds_table.bump
+    )]
+    pub odds_table: Account<'info, OddsTable>,
+}
+
+pub fn update_odds_table(
+    ctx: Context<UpdateOddsTable>,
+    weights: [u16; RARITY_COUNT],
+    xp_rewards: [u32; RARITY_COUNT],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let odds_table = &mut ctx.accounts.odds_table;
+
+    // Weights must add up to 100%
+    validate_odds_table(&weights)?;
+
+    odds_table.weights = weights;
+    odds_table.xp_rewards = xp_rewards;
+    odds_table.updated_at = clock.unix_timestamp;
+
+    emit!(OddsTableUpdated {
+        odds_table: odds_table.key(),
+        id: odds_table.id,
+        weights,
+        xp_rewards,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct OddsTableUpdated {
+    pub odds_table: Pubkey,
+    pub id: u32,
+    pub weights: [u16; RARITY_COUNT],
+    pub xp_rewards: [u32; RARITY_COUNT],
+    pub timestamp: i64,
+}
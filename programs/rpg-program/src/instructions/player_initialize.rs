@@ -1,6 +1,5 @@
 use anchor_lang::prelude::*;
 use crate::constants::*;
-use crate::errors::RpgError;
 use crate::state::*;
 
 #[derive(Accounts)]
@@ -17,6 +16,14 @@ pub struct PlayerInitialize<'info> {
     )]
     pub player: Account<'info, Player>,
 
+    /// Player who referred this one, stored permanently
+    #[account(
+        mut,
+        seeds = [PLAYER_SEED, referrer.authority.as_ref()],
+        bump = referrer.bump
+    )]
+    pub referrer: Option<Account<'info, Player>>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -30,12 +37,26 @@ pub fn player_initialize(ctx: Context<PlayerInitialize>) -> Result<()> {
     player.total_victories = 0;
     player.total_xp_earned = 0;
     player.total_sol_spent = 0;
+    player.gold = 0;
+    player.total_gold_earned = 0;
+    player.total_gold_spent = 0;
+    player.referrer = None;
+    player.referral_count = 0;
+    player.referee_sol_spent = 0;
+    player.referral_balance = 0;
+    player.total_referral_earned = 0;
     player.created_at = clock.unix_timestamp;
     player.bump = ctx.bumps.player;
 
+    if let Some(referrer) = &mut ctx.accounts.referrer {
+        player.referrer = Some(referrer.key());
+        referrer.referral_count = referrer.referral_count.saturating_add(1);
+    }
+
     emit!(PlayerInitialized {
         player: player.key(),
         authority: player.authority,
+        referrer: player.referrer,
         timestamp: clock.unix_timestamp,
     });
 
@@ -46,5 +67,6 @@ pub fn player_initialize(ctx: Context<PlayerInitialize>) -> Result<()> {
 pub struct PlayerInitialized {
     pub player: Pubkey,
     pub authority: Pubkey,
+    pub referrer: Option<Pubkey>,
     pub timestamp: i64,
 }
\ No newline at end of file
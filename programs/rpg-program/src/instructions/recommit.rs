@@ -0,0 +1,197 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+use crate::utils::randomness::validate_recommit;
+
+#[derive(Accounts)]
+pub struct RecommitRoll<'info> {
+    pub player_authority: Signer<'info>,
+
+    #[account(
+        seeds = [PLAYER_SEED, player_authority.key().as_ref()],
+        bump = player.bump,
+        constraint = player.authority == player_authority.key() @ RpgError::Unauthorized
+    )]
+    pub player: Account<'info, Player>,
+
+    #[account(
+        seeds = [HERO_SEED, player.key().as_ref(), &hero.index.to_le_bytes()],
+        bump = hero.bump,
+        constraint = hero.owner == player_authority.key() @ RpgError::Unauthorized,
+        constraint = hero.player == player.key() @ RpgError::HeroNotFound
+    )]
+    pub hero: Account<'info, Hero>,
+
+    #[account(
+        mut,
+        seeds = [ROLL_SESSION_SEED, hero.key().as_ref(), &roll_session.nonce.to_le_bytes()],
+        bump = roll_session.bump,
+        constraint = roll_session.hero == hero.key() @ RpgError::RollSessionNotFound,
+        constraint = roll_session.player == player.key() @ RpgError::RollSessionNotFound,
+        constraint = roll_session.is_pending() @ RpgError::RollAlreadyCompleted
+    )]
+    pub roll_session: Account<'info, RollSession>,
+
+    /// CHECK: Checked manually for slot hashes sysvar
+    pub slot_hashes: AccountInfo<'info>,
+}
+
+pub fn recommit_roll(ctx: Context<RecommitRoll>) -> Result<()> {
+    let clock = Clock::get()?;
+    let roll_session = &mut ctx.accounts.roll_session;
+
+    // Only sessions whose reveal slot was skipped, a limited number of times
+    validate_recommit(
+        roll_session.randomness_provider,
+        &ctx.accounts.slot_hashes,
+        roll_session.commit_slot,
+        roll_session.recommit_count,
+    )?;
+
+    let previous_commit_slot = roll_session.commit_slot;
+    roll_session.commit_slot = clock.slot;
+    roll_session.recommit_count += 1;
+
+    emit!(RandomnessRecommitted {
+        player: ctx.accounts.player.key(),
+        hero: ctx.accounts.hero.key(),
+        session: roll_session.key(),
+        previous_commit_slot,
+        commit_slot: clock.slot,
+        recommit_count: roll_session.recommit_count,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RecommitBattle<'info> {
+    pub player_authority: Signer<'info>,
+
+    #[account(
+        seeds = [PLAYER_SEED, player_authority.key().as_ref()],
+        bump = player.bump,
+        constraint = player.authority == player_authority.key() @ RpgError::Unauthorized
+    )]
+    pub player: Account<'info, Player>,
+
+    #[account(
+        seeds = [HERO_SEED, player.key().as_ref(), &hero.index.to_le_bytes()],
+        bump = hero.bump,
+        constraint = hero.owner == player_authority.key() @ RpgError::Unauthorized,
+        constraint = hero.player == player.key() @ RpgError::HeroNotFound
+    )]
+    pub hero: Account<'info, Hero>,
+
+    #[account(
+        mut,
+        seeds = [BATTLE_SEED, hero.key().as_ref(), &battle.nonce.to_le_bytes()],
+        bump = battle.bump,
+        constraint = battle.hero == hero.key() @ RpgError::BattleNotFound,
+        constraint = battle.turn == 0 @ RpgError::BattleAlreadyCompleted  // Seed is fixed after the first turn
+    )]
+    pub battle: Account<'info, Battle>,
+
+    /// CHECK: Checked manually for slot hashes sysvar
+    pub slot_hashes: AccountInfo<'info>,
+}
+
+pub fn recommit_battle(ctx: Context<RecommitBattle>) -> Result<()> {
+    let clock = Clock::get()?;
+    let battle = &mut ctx.accounts.battle;
+
+    // Only battles whose reveal slot was skipped, a limited number of times
+    validate_recommit(
+        battle.randomness_provider,
+        &ctx.accounts.slot_hashes,
+        battle.commit_slot,
+        battle.recommit_count,
+    )?;
+
+    let previous_commit_slot = battle.commit_slot;
+    battle.commit_slot = clock.slot;
+    battle.recommit_count += 1;
+
+    emit!(RandomnessRecommitted {
+        player: ctx.accounts.player.key(),
+        hero: ctx.accounts.hero.key(),
+        session: battle.key(),
+        previous_commit_slot,
+        commit_slot: clock.slot,
+        recommit_count: battle.recommit_count,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RecommitBattleAction<'info> {
+    pub player_authority: Signer<'info>,
+
+    #[account(
+        seeds = [PLAYER_SEED, player_authority.key().as_ref()],
+        bump = player.bump,
+        constraint = player.authority == player_authority.key() @ RpgError::Unauthorized
+    )]
+    pub player: Account<'info, Player>,
+
+    #[account(
+        seeds = [HERO_SEED, player.key().as_ref(), &hero.index.to_le_bytes()],
+        bump = hero.bump,
+        constraint = hero.owner == player_authority.key() @ RpgError::Unauthorized,
+        constraint = hero.player == player.key() @ RpgError::HeroNotFound
+    )]
+    pub hero: Account<'info, Hero>,
+
+    #[account(
+        mut,
+        seeds = [BATTLE_SEED, hero.key().as_ref(), &battle.nonce.to_le_bytes()],
+        bump = battle.bump,
+        constraint = battle.hero == hero.key() @ RpgError::BattleNotFound,
+        constraint = battle.is_active() @ RpgError::BattleAlreadyCompleted,
+        constraint = battle.per_turn_randomness @ RpgError::PerTurnRandomnessDisabled
+    )]
+    pub battle: Account<'info, Battle>,
+
+    /// CHECK: Checked manually for slot hashes sysvar
+    pub slot_hashes: AccountInfo<'info>,
+}
+
+pub fn recommit_battle_action(ctx: Context<RecommitBattleAction>) -> Result<()> {
+    let clock = Clock::get()?;
+    let battle = &mut ctx.accounts.battle;
+
+    // Turn entropy always comes from slot hashes, whatever the battle's seed provider.
+    // The recommit limit is shared with recommit_battle; past it, or once the reveal slot's hash
+    // has expired, the battle can only be forfeited.
+    validate_recommit(
+        RANDOMNESS_PROVIDER_SLOT_HASHES,
+        &ctx.accounts.slot_hashes,
+        battle.action_commit_slot,
+        battle.recommit_count,
+    )?;
+
+    let previous_commit_slot = battle.recommit_action(clock.slot)?;
+
+    emit!(RandomnessRecommitted {
+        player: ctx.accounts.player.key(),
+        hero: ctx.accounts.hero.key(),
+        session: battle.key(),
+        previous_commit_slot,
+        commit_slot: clock.slot,
+        recommit_count: battle.recommit_count,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct RandomnessRecommitted {
+    pub player: Pubkey,
+    pub hero: Pubkey,
+    pub session: Pubkey, // Roll session or battle
+    pub previous_commit_slot: u64,
+    pub commit_slot: u64,
+    pub recommit_count: u8,
+}
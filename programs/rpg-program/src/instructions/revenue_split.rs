@@ -0,0 +1,115 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+use crate::utils::validation::*;
+use crate::utils::vault::transfer_from_vault;
+
+#[derive(Accounts)]
+pub struct SetRevenueSplit<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        constraint = treasury.authority == authority.key() @ RpgError::Unauthorized
+    )]
+    pub treasury: Account<'info, Treasury>,
+}
+
+/// Change how future SOL fees are divided, existing bucket balances are kept
+pub fn set_revenue_split(
+    ctx: Context<SetRevenueSplit>,
+    shares: [u16; REVENUE_BUCKET_COUNT],
+    recipients: [Pubkey; REVENUE_BUCKET_COUNT],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let treasury = &mut ctx.accounts.treasury;
+
+    // Validate shares cover every fee exactly
+    validate_revenue_split(&shares)?;
+
+    treasury.revenue_shares = shares;
+    treasury.bucket_recipients = recipients;
+
+    emit!(RevenueSplitUpdated {
+        treasury: treasury.key(),
+        shares,
+        recipients,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(bucket: u8)]
+pub struct ClaimRevenue<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        constraint = treasury.bucket_recipients.get(bucket as usize) == Some(&recipient.key()) @ RpgError::Unauthorized
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED],
+        bump = treasury.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Pay out a bucket's whole balance to its recipient
+pub fn claim_revenue(ctx: Context<ClaimRevenue>, bucket: u8) -> Result<()> {
+    let clock = Clock::get()?;
+    let treasury = &mut ctx.accounts.treasury;
+
+    let amount = treasury.claim_bucket(bucket as usize)?;
+
+    // Other buckets, referral rewards and escrow stay in the vault
+    transfer_from_vault(
+        &ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        treasury.vault_bump,
+        treasury.allocated_balance(),
+        amount,
+    )?;
+
+    emit!(RevenueClaimed {
+        treasury: treasury.key(),
+        bucket,
+        recipient: ctx.accounts.recipient.key(),
+        amount,
+        total_claimed: treasury.bucket_claimed[bucket as usize],
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct RevenueSplitUpdated {
+    pub treasury: Pubkey,
+    pub shares: [u16; REVENUE_BUCKET_COUNT],
+    pub recipients: [Pubkey; REVENUE_BUCKET_COUNT],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RevenueClaimed {
+    pub treasury: Pubkey,
+    pub bucket: u8,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub total_claimed: u64,
+    pub timestamp: i64,
+}
@@ -1,9 +1,9 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::sysvar::slot_hashes;
 use crate::constants::*;
 use crate::errors::RpgError;
 use crate::state::*;
-use crate::utils::validation::*;
+use crate::utils::items::create_item;
+use crate::utils::randomness::*;
 use crate::utils::rng::*;
 
 #[derive(Accounts)]
@@ -38,71 +38,159 @@ pub struct RollFulfill<'info> {
     )]
     pub roll_session: Account<'info, RollSession>,
 
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
     /// CHECK: Checked manually for slot hashes sysvar
     pub slot_hashes: AccountInfo<'info>,
+
+    /// CHECK: Owner and requester checked in utils::randomness, only used by the oracle provider
+    pub oracle_request: Option<UncheckedAccount<'info>>,
+
+    /// Banner the session was started on, needed for its featured rewards
+    #[account(
+        seeds = [BANNER_SEED, &banner.id.to_le_bytes()],
+        bump = banner.bump,
+        constraint = Some(banner.key()) == roll_session.banner @ RpgError::InvalidBanner
+    )]
+    pub banner: Option<Account<'info, Banner>>,
+
+    /// CHECK: Item PDA, only created when a featured reward is won
+    #[account(mut)]
+    pub featured_item: Option<UncheckedAccount<'info>>,
+
+    /// Referrer of the player, required when the entry earned them a reward
+    #[account(
+        mut,
+        constraint = Some(referrer.key()) == player.referrer @ RpgError::InvalidReferrer
+    )]
+    pub referrer: Option<Account<'info, Player>>,
+
+    pub system_program: Program<'info, System>,
 }
 
-pub fn roll_fulfill(ctx: Context<RollFulfill>) -> Result<()> {
+pub fn roll_fulfill(ctx: Context<RollFulfill>, secret: [u8; 32]) -> Result<()> {
     let clock = Clock::get()?;
     let player = &mut ctx.accounts.player;
     let hero = &mut ctx.accounts.hero;
     let roll_session = &mut ctx.accounts.roll_session;
 
-    // Validate randomness delay
-    validate_randomness_delay(clock.slot, roll_session.commit_slot)?;
-
-    let reveal_slot = roll_session.commit_slot + MIN_RANDOMNESS_DELAY_SLOTS;
-
-    // Validate slot hashes sysvar
-    let sysvar_slot_history = &ctx.accounts.slot_hashes;
+    // Banner sessions need their banner to resolve featured rewards
     require!(
-        sysvar_slot_history.key == &slot_hashes::id(),
-        RpgError::InvalidSlotHashes
+        roll_session.banner.is_none() || ctx.accounts.banner.is_some(),
+        RpgError::InvalidBanner
     );
 
-    // Extract slot hash for randomness
-    let data = sysvar_slot_history.try_borrow_data()?;
-    let num_slot_hashes = u64::from_le_bytes(data[0..8].try_into().unwrap());
-    let mut pos = 8;
-    let mut found_hash = None;
-
-    for _ in 0..num_slot_hashes {
-        let slot = u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap());
-        pos += 8;
-        let hash = &data[pos..pos + 32];
-        if slot == reveal_slot {
-            found_hash = Some(hash);
-            break;
-        }
-        pos += 32;
-    }
-
-    let random_seed = found_hash.ok_or(RpgError::SlotNotFound)?;
-
-    // Convert slice to array for RNG functions
-    let mut seed_array = [0u8; 32];
-    seed_array.copy_from_slice(random_seed);
-
-    // Determine reward rarity using ponzimon-style RNG
-    let rarity = determine_roll_rarity(&seed_array, 0);
-
-    // Get XP reward based on rarity
-    let xp_reward = ROLL_REWARDS[rarity as usize];
-
-    // Award XP to hero
-    hero.add_xp(xp_reward);
+    // The revealed secret must match the one committed at roll start
+    verify_player_secret(&roll_session.secret_hash, &secret)?;
+
+    // Reveal randomness from the provider chosen at roll start
+    let randomness = reveal_randomness(
+        roll_session.randomness_provider,
+        roll_session.oracle(ctx.accounts.oracle_request.as_ref().map(|request| request.as_ref())),
+        &roll_session.key(),
+        roll_session.commit_slot,
+        clock.slot,
+        &ctx.accounts.slot_hashes,
+    )?;
+    let seed_array = mix_player_secret(&randomness, &secret);
+
+    // Every roll draws independently from the one seed, luck and pity improve the odds.
+    // The odds are the ones snapshotted when the session was paid for.
+    // Gear only counts in battle, rolls use the hero's own luck
+    let economy = &ctx.accounts.game_config.economy;
+    let luck = hero.base_attributes.luck;
+    let roll_count = roll_session.roll_count as usize;
+    let mut rarities = [0u8; ROLL_BATCH_SIZE];
+    draw_roll_rarities(
+        &seed_array,
+        &mut rarities[..roll_count],
+        &roll_session.odds_weights,
+        &mut hero.roll_pity,
+        luck,
+        economy,
+    );
 
-    // Complete roll session
-    roll_session.complete_roll(rarity, xp_reward);
+    // Award XP for every roll
+    let mut xp_reward = 0u32;
+    for &rarity in &rarities[..roll_count] {
+        let xp = roll_session.odds_xp_reward(rarity);
+        hero.add_xp(xp);
+        xp_reward = xp_reward.saturating_add(xp);
+    }
 
-    // Update player stats
+    // Update player stats once per session
     player.add_battle_stats(true, xp_reward as u64); // Count as victory for XP tracking
 
+    // Complete roll session, its fee can't be refunded anymore
+    roll_session.complete_rolls(&rarities[..roll_count], xp_reward);
+    match roll_session.payment_mint {
+        Some(mint) => ctx
+            .accounts
+            .treasury
+            .release_token_entry(&mint, roll_session.cost, RevenueType::Roll)?,
+        None => {
+            let referral = referral_for(&mut ctx.accounts.referrer, roll_session.referral_reward)?;
+            ctx.accounts
+                .treasury
+                .release_entry(roll_session.cost, RevenueType::Roll, referral)
+        }
+    }
+    let best_rarity = roll_session.reward_rarity.unwrap_or_default();
+
+    // A good enough session on a banner also wins an item from its featured pool
+    if let Some(banner) = &ctx.accounts.banner {
+        if best_rarity >= banner.featured_min_rarity {
+            if let Some((item_type, rarity, element)) =
+                roll_loot(&seed_array, 0, banner.active_featured(), luck, economy)
+            {
+                let featured_item = ctx
+                    .accounts
+                    .featured_item
+                    .as_ref()
+                    .ok_or(RpgError::InvalidLootItemAccount)?;
+                let roll_session_key = roll_session.key();
+                let item = Item::generate(hero.key(), item_type, rarity, element, clock.unix_timestamp);
+                let item_key = create_item(
+                    &ctx.accounts.player_authority.to_account_info(),
+                    &featured_item.to_account_info(),
+                    &ctx.accounts.system_program.to_account_info(),
+                    ctx.program_id,
+                    hero,
+                    item,
+                )?;
+
+                emit!(FeaturedRewardWon {
+                    hero: hero.key(),
+                    roll_session: roll_session_key,
+                    banner: banner.key(),
+                    item: item_key,
+                    item_type,
+                    rarity,
+                    element,
+                });
+            }
+        }
+    }
+
     emit!(RollCompleted {
         player: player.key(),
         hero: hero.key(),
-        rarity,
+        banner: roll_session.banner,
+        rarity: best_rarity,
         xp_reward,
+        rarities: rarities[..roll_count].to_vec(),
+        pity: hero.roll_pity,
         slot: clock.slot,
     });
 
@@ -113,7 +201,21 @@ pub fn roll_fulfill(ctx: Context<RollFulfill>) -> Result<()> {
 pub struct RollCompleted {
     pub player: Pubkey,
     pub hero: Pubkey,
-    pub rarity: u8,
-    pub xp_reward: u32,
+    pub banner: Option<Pubkey>,
+    pub rarity: u8,        // Best rarity of the session
+    pub xp_reward: u32,    // Total XP of the session
+    pub rarities: Vec<u8>, // Every roll in order
+    pub pity: u16,         // Hero's pity counter after the session
     pub slot: u64,
-}
\ No newline at end of file
+}
+
+#[event]
+pub struct FeaturedRewardWon {
+    pub hero: Pubkey,
+    pub roll_session: Pubkey,
+    pub banner: Pubkey,
+    pub item: Pubkey,
+    pub item_type: u8,
+    pub rarity: u8,
+    pub element: u8,
+}
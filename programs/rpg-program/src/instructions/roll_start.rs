@@ -1,12 +1,15 @@
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
+use anchor_spl::token::{Token, TokenAccount};
 use crate::constants::*;
 use crate::errors::RpgError;
 use crate::state::*;
+use crate::utils::math::calculate_referral_reward;
 use crate::utils::validation::*;
+use crate::utils::vault::TokenPaymentAccounts;
 
 #[derive(Accounts)]
-#[instruction(nonce: u64)]
+#[instruction(nonce: u64, secret_hash: [u8; 32])]
 pub struct RollStart<'info> {
     #[account(mut)]
     pub player_authority: Signer<'info>,
@@ -37,6 +40,25 @@ pub struct RollStart<'info> {
     )]
     pub roll_session: Account<'info, RollSession>,
 
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(
+        seeds = [ODDS_TABLE_SEED, &odds_table.id.to_le_bytes()],
+        bump = odds_table.bump
+    )]
+    pub odds_table: Account<'info, OddsTable>,
+
+    /// Banner to roll on, standard rolls use the standard odds table without one
+    #[account(
+        seeds = [BANNER_SEED, &banner.id.to_le_bytes()],
+        bump = banner.bump
+    )]
+    pub banner: Option<Account<'info, Banner>>,
+
     #[account(
         mut,
         seeds = [TREASURY_SEED],
@@ -44,50 +66,146 @@ pub struct RollStart<'info> {
     )]
     pub treasury: Account<'info, Treasury>,
 
+    #[account(
+        mut,
+        seeds = [VAULT_SEED],
+        bump = treasury.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    /// Player's token account, pass with the treasury token account to pay in the configured mint
+    #[account(
+        mut,
+        constraint = player_token_account.owner == player_authority.key() @ RpgError::InvalidPaymentAccount
+    )]
+    pub player_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_TOKEN_SEED, treasury_token_account.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
     pub system_program: Program<'info, System>,
 }
 
-pub fn roll_start(ctx: Context<RollStart>, nonce: u64) -> Result<()> {
+pub fn roll_start(ctx: Context<RollStart>, nonce: u64, secret_hash: [u8; 32]) -> Result<()> {
+    start_rolls(ctx, nonce, secret_hash, 1)
+}
+
+/// Buy ROLL_BATCH_SIZE rolls at the batch price, resolved together by one roll_fulfill
+pub fn roll_start_batch(ctx: Context<RollStart>, nonce: u64, secret_hash: [u8; 32]) -> Result<()> {
+    start_rolls(ctx, nonce, secret_hash, ROLL_BATCH_SIZE as u8)
+}
+
+fn start_rolls(ctx: Context<RollStart>, nonce: u64, secret_hash: [u8; 32], roll_count: u8) -> Result<()> {
     let clock = Clock::get()?;
     let player = &mut ctx.accounts.player;
     let hero = &ctx.accounts.hero;
     let roll_session = &mut ctx.accounts.roll_session;
     let treasury = &mut ctx.accounts.treasury;
+    let economy = &ctx.accounts.game_config.economy;
+    let odds_table = &ctx.accounts.odds_table;
 
-    // Validate sufficient funds
-    validate_sufficient_funds(
-        ctx.accounts.player_authority.lamports(),
-        ROLL_COST,
-    )?;
+    // Banners set their own price and odds and only accept rolls while live
+    let (single_cost, batch_cost) = match &ctx.accounts.banner {
+        Some(banner) => {
+            require!(banner.is_live(clock.unix_timestamp), RpgError::BannerNotLive);
+            require_keys_eq!(odds_table.key(), banner.odds_table, RpgError::InvalidOddsTable);
+            (banner.roll_cost, banner.roll_batch_cost)
+        }
+        None => {
+            require!(odds_table.id == STANDARD_ODDS_TABLE_ID, RpgError::InvalidOddsTable);
+            (economy.roll_cost, economy.roll_batch_cost)
+        }
+    };
 
-    // Transfer SOL to treasury
-    system_program::transfer(
-        CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            system_program::Transfer {
-                from: ctx.accounts.player_authority.to_account_info(),
-                to: treasury.to_account_info(),
-            },
-        ),
-        ROLL_COST,
+    // Pay in the configured token when token accounts are passed, SOL otherwise
+    let token_accounts = TokenPaymentAccounts::from_optional(
+        &ctx.accounts.player_token_account,
+        &ctx.accounts.treasury_token_account,
+        &ctx.accounts.token_program,
     )?;
+    let (cost, payment_mint, referral_reward) = match token_accounts {
+        Some(token_accounts) => {
+            // Banner prices only exist in lamports
+            require!(ctx.accounts.banner.is_none(), RpgError::BannerRequiresSol);
+            let token_payment = ctx
+                .accounts
+                .game_config
+                .token_payment
+                .ok_or(RpgError::TokenPaymentDisabled)?;
+            let cost = if roll_count > 1 {
+                token_payment.roll_batch_cost
+            } else {
+                token_payment.roll_cost
+            };
+            token_accounts.collect(&ctx.accounts.player_authority, &token_payment.mint, cost)?;
+            treasury.escrow_token_entry(&token_payment.mint, cost)?;
+            (cost, Some(token_payment.mint), 0)
+        }
+        None => {
+            let cost = if roll_count > 1 { batch_cost } else { single_cost };
+
+            // Validate sufficient funds
+            validate_sufficient_funds(
+                ctx.accounts.player_authority.lamports(),
+                cost,
+            )?;
+
+            // Transfer SOL to the treasury vault
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.player_authority.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                    },
+                ),
+                cost,
+            )?;
+
+            // Held in escrow until the randomness is revealed, a cancel refunds it
+            treasury.escrow_entry(cost);
+            player.add_sol_spent(cost);
+
+            // Referrers earn a share of SOL spending, credited once the entry resolves
+            let referral_reward = match player.referrer {
+                Some(_) => calculate_referral_reward(&ctx.accounts.game_config.economy, cost),
+                None => 0,
+            };
+            (cost, None, referral_reward)
+        }
+    };
 
     // Initialize roll session
     roll_session.hero = hero.key();
     roll_session.player = player.key();
     roll_session.nonce = nonce;
     roll_session.commit_slot = clock.slot;
+    roll_session.recommit_count = 0;
+    roll_session.randomness_provider = ctx.accounts.game_config.randomness_provider;
+    roll_session.oracle_program = ctx.accounts.game_config.oracle_program;
+    roll_session.secret_hash = secret_hash;
+    roll_session.odds_table = odds_table.key();
+    roll_session.banner = ctx.accounts.banner.as_ref().map(|banner| banner.key());
+    roll_session.cost = cost;
+    roll_session.payment_mint = payment_mint;
+    roll_session.referral_reward = referral_reward;
     roll_session.state = ROLL_STATE_PENDING;
+    roll_session.roll_count = roll_count;
+    roll_session.rarities = [0u8; ROLL_BATCH_SIZE];
     roll_session.reward_rarity = None;
     roll_session.reward_xp = None;
     roll_session.created_at = clock.unix_timestamp;
     roll_session.bump = ctx.bumps.roll_session;
-
-    // Update treasury stats
-    treasury.add_revenue(ROLL_COST, crate::state::treasury::RevenueType::Roll);
-
-    // Update player stats
-    player.add_sol_spent(ROLL_COST);
+    // Later odds table updates don't touch sessions already paid for
+    roll_session.odds_weights = odds_table.weights;
+    roll_session.odds_xp_rewards = odds_table.xp_rewards;
+    roll_session.oracle_layout = ctx.accounts.game_config.oracle_layout;
 
     Ok(())
 }
\ No newline at end of file
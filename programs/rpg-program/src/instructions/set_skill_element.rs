@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+use crate::utils::validation::*;
+
+#[derive(Accounts)]
+pub struct SetSkillElement<'info> {
+    #[account(mut)]
+    pub player_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_SEED, player_authority.key().as_ref()],
+        bump = player.bump,
+        constraint = player.authority == player_authority.key() @ RpgError::Unauthorized
+    )]
+    pub player: Account<'info, Player>,
+
+    #[account(
+        mut,
+        seeds = [HERO_SEED, player.key().as_ref(), &hero.index.to_le_bytes()],
+        bump = hero.bump,
+        constraint = hero.owner == player_authority.key() @ RpgError::Unauthorized,
+        constraint = hero.player == player.key() @ RpgError::HeroNotFound
+    )]
+    pub hero: Account<'info, Hero>,
+
+    #[account(
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+pub fn set_skill_element(ctx: Context<SetSkillElement>, element: u8) -> Result<()> {
+    let player = &mut ctx.accounts.player;
+    let hero = &mut ctx.accounts.hero;
+
+    // Validate element
+    validate_element(element)?;
+    require!(hero.elemental.element != element, RpgError::InvalidElement);
+
+    // Every change costs gold, so countering each enemy's element isn't free
+    let cost = ctx.accounts.game_config.economy.element_change_cost;
+    player.spend_gold(cost)?;
+
+    // Battles snapshot the element at start, so running battles are unaffected
+    hero.elemental.element = element;
+
+    emit!(SkillElementChanged {
+        player: player.key(),
+        hero: hero.key(),
+        element,
+        gold_spent: cost,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct SkillElementChanged {
+    pub player: Pubkey,
+    pub hero: Pubkey,
+    pub element: u8,
+    pub gold_spent: u64,
+}
@@ -0,0 +1,122 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct CreateTreasuryTokenAccount<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        constraint = treasury.authority == authority.key() @ RpgError::Unauthorized
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        seeds = [VAULT_SEED],
+        bump = treasury.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [TREASURY_TOKEN_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Create the vault-owned token account that collects payments in `mint`
+pub fn create_treasury_token_account(ctx: Context<CreateTreasuryTokenAccount>) -> Result<()> {
+    let clock = Clock::get()?;
+    let treasury = &mut ctx.accounts.treasury;
+    let mint = ctx.accounts.mint.key();
+
+    // Revenue in this mint is tracked from now on
+    treasury.register_mint(mint)?;
+
+    emit!(TreasuryTokenAccountCreated {
+        treasury: treasury.key(),
+        mint,
+        token_account: ctx.accounts.treasury_token_account.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetTokenPayment<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GAME_CONFIG_SEED],
+        bump = game_config.bump,
+        constraint = game_config.authority == authority.key() @ RpgError::Unauthorized
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+}
+
+/// Accept `token_payment.mint` at its prices, or SOL only with None
+pub fn set_token_payment(
+    ctx: Context<SetTokenPayment>,
+    token_payment: Option<TokenPaymentConfig>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let game_config = &mut ctx.accounts.game_config;
+
+    // Payments need a treasury token account for the mint
+    if let Some(token_payment) = &token_payment {
+        require!(
+            ctx.accounts.treasury.mint_revenue(&token_payment.mint).is_some(),
+            RpgError::InvalidPaymentMint
+        );
+    }
+
+    // Pending sessions keep the mint and cost they were paid with
+    game_config.token_payment = token_payment;
+    game_config.updated_at = clock.unix_timestamp;
+
+    emit!(TokenPaymentChanged {
+        game_config: game_config.key(),
+        token_payment,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct TreasuryTokenAccountCreated {
+    pub treasury: Pubkey,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokenPaymentChanged {
+    pub game_config: Pubkey,
+    pub token_payment: Option<TokenPaymentConfig>,
+    pub timestamp: i64,
+}
@@ -0,0 +1,189 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::*;
+use crate::utils::validation::validate_treasury_withdrawal;
+use crate::utils::vault::transfer_from_vault;
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        constraint = treasury.authority == authority.key() @ RpgError::Unauthorized
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_SEED],
+        bump = treasury.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub recipient: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Pay out of the dev bucket, keeping the part of the reserve it holds back
+pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+    let clock = Clock::get()?;
+    let treasury = &mut ctx.accounts.treasury;
+
+    treasury.withdraw_dev(amount)?;
+
+    // Everything still allocated, the held back reserve included, stays in the vault
+    transfer_from_vault(
+        &ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        treasury.vault_bump,
+        treasury.allocated_balance(),
+        amount,
+    )?;
+
+    emit!(TreasuryWithdrawn {
+        treasury: treasury.key(),
+        recipient: ctx.accounts.recipient.key(),
+        amount,
+        balance: ctx.accounts.vault.lamports(),
+        total_withdrawn: treasury.total_withdrawn,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasuryTokens<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        constraint = treasury.authority == authority.key() @ RpgError::Unauthorized
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        seeds = [VAULT_SEED],
+        bump = treasury.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_TOKEN_SEED, treasury_token_account.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = recipient_token_account.mint == treasury_token_account.mint @ RpgError::InvalidPaymentMint
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Withdraw token revenue, entry fees still in escrow stay for refunds
+pub fn withdraw_treasury_tokens(ctx: Context<WithdrawTreasuryTokens>, amount: u64) -> Result<()> {
+    let clock = Clock::get()?;
+    let treasury = &mut ctx.accounts.treasury;
+    let mint = ctx.accounts.treasury_token_account.mint;
+
+    let escrow = treasury
+        .mint_revenue(&mint)
+        .map_or(0, |revenue| revenue.escrow);
+    validate_treasury_withdrawal(ctx.accounts.treasury_token_account.amount, escrow, amount)?;
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.treasury_token_account.to_account_info(),
+                to: ctx.accounts.recipient_token_account.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            },
+            &[&[VAULT_SEED, &[treasury.vault_bump]]],
+        ),
+        amount,
+    )?;
+    treasury.record_token_withdrawal(&mint, amount)?;
+
+    ctx.accounts.treasury_token_account.reload()?;
+    emit!(TreasuryTokensWithdrawn {
+        treasury: treasury.key(),
+        mint,
+        recipient: ctx.accounts.recipient_token_account.key(),
+        amount,
+        balance: ctx.accounts.treasury_token_account.amount,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetTreasuryReserve<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        constraint = treasury.authority == authority.key() @ RpgError::Unauthorized
+    )]
+    pub treasury: Account<'info, Treasury>,
+}
+
+pub fn set_treasury_reserve(ctx: Context<SetTreasuryReserve>, reserve: u64) -> Result<()> {
+    let clock = Clock::get()?;
+    let treasury = &mut ctx.accounts.treasury;
+
+    // Pending escrow is always kept as part of the allocated balance, so any reserve is safe
+    treasury.reserve = reserve;
+
+    emit!(TreasuryReserveUpdated {
+        treasury: treasury.key(),
+        reserve,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub treasury: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub balance: u64, // Vault lamports after the withdrawal
+    pub total_withdrawn: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryReserveUpdated {
+    pub treasury: Pubkey,
+    pub reserve: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryTokensWithdrawn {
+    pub treasury: Pubkey,
+    pub mint: Pubkey,
+    pub recipient: Pubkey, // Token account
+    pub amount: u64,
+    pub balance: u64, // Treasury token account balance after the withdrawal
+    pub timestamp: i64,
+}
@@ -6,7 +6,6 @@ pub mod state;
 pub mod instructions;
 pub mod utils;
 
-use errors::RpgError;
 use instructions::*;
 
 declare_id!("6cLSu9TdHVztKk2pykJGArnuhmXmrc1agezqVWAK9ubp");
@@ -15,17 +14,18 @@ declare_id!("6cLSu9TdHVztKk2pykJGArnuhmXmrc1agezqVWAK9ubp");
 pub mod rpg_program {
     use super::*;
 
-    /// Initialize a new player profile
+    /// Initialize a new player profile, optionally recording the referrer's Player account
     pub fn player_initialize(ctx: Context<PlayerInitialize>) -> Result<()> {
         instructions::player_initialize(ctx)
     }
 
-    /// Purchase a new hero
-    pub fn buy_hero(ctx: Context<BuyHero>, hero_index: u8) -> Result<()> {
-        instructions::buy_hero(ctx, hero_index)
+    /// Purchase a new hero of the chosen class
+    pub fn buy_hero(ctx: Context<BuyHero>, hero_index: u8, hero_class: u8) -> Result<()> {
+        instructions::buy_hero(ctx, hero_index, hero_class)
     }
 
     /// Level up a hero with attribute distribution
+    #[allow(clippy::too_many_arguments)]
     pub fn level_up_hero(
         ctx: Context<LevelUpHero>,
         strength: u16,
@@ -50,24 +50,45 @@ pub mod rpg_program {
         )
     }
 
-    /// Start a slot machine roll (commit phase)
-    pub fn roll_start(ctx: Context<RollStart>, nonce: u64) -> Result<()> {
-        instructions::roll_start(ctx, nonce)
+    /// Start a slot machine roll, committing to the hash of a player secret (commit phase)
+    pub fn roll_start(ctx: Context<RollStart>, nonce: u64, secret_hash: [u8; 32]) -> Result<()> {
+        instructions::roll_start(ctx, nonce, secret_hash)
     }
 
-    /// Fulfill a slot machine roll with randomness (reveal phase)
-    pub fn roll_fulfill(ctx: Context<RollFulfill>) -> Result<()> {
-        instructions::roll_fulfill(ctx)
+    /// Start a discounted batch of rolls resolved by a single reveal
+    pub fn roll_start_batch(ctx: Context<RollStart>, nonce: u64, secret_hash: [u8; 32]) -> Result<()> {
+        instructions::roll_start_batch(ctx, nonce, secret_hash)
     }
 
-    /// Start a battle against an enemy
-    pub fn battle_start(ctx: Context<BattleStart>, nonce: u64) -> Result<()> {
-        instructions::battle_start(ctx, nonce)
+    /// Fulfill a slot machine roll with randomness and the revealed secret (reveal phase)
+    pub fn roll_fulfill(ctx: Context<RollFulfill>, secret: [u8; 32]) -> Result<()> {
+        instructions::roll_fulfill(ctx, secret)
     }
 
-    /// Execute a battle turn
-    pub fn battle_turn(ctx: Context<BattleTurn>, action: u8) -> Result<()> {
-        instructions::battle_turn(ctx, action)
+    /// Start a battle against an enemy, committing to the hash of a player secret.
+    /// Per-turn randomness battles commit each action before it is resolved.
+    pub fn battle_start(
+        ctx: Context<BattleStart>,
+        nonce: u64,
+        secret_hash: [u8; 32],
+        per_turn_randomness: bool,
+    ) -> Result<()> {
+        instructions::battle_start(ctx, nonce, secret_hash, per_turn_randomness)
+    }
+
+    /// Commit the next action of a per-turn randomness battle
+    pub fn battle_commit_action(ctx: Context<BattleCommitAction>, action: u8) -> Result<()> {
+        instructions::battle_commit_action(ctx, action)
+    }
+
+    /// Execute a battle turn, the first turn reveals the player secret
+    pub fn battle_turn(ctx: Context<BattleTurn>, action: u8, secret: Option<[u8; 32]>) -> Result<()> {
+        instructions::battle_turn(ctx, action, secret)
+    }
+
+    /// Give up an active battle, settled as a loss
+    pub fn forfeit_battle(ctx: Context<ForfeitBattle>) -> Result<()> {
+        instructions::forfeit_battle(ctx)
     }
 
     /// Settle a completed battle
@@ -75,7 +96,18 @@ pub mod rpg_program {
         instructions::battle_settle(ctx)
     }
 
+    /// Close a settled battle and refund its rent to the hero owner
+    pub fn close_battle(ctx: Context<CloseBattle>) -> Result<()> {
+        instructions::close_battle(ctx)
+    }
+
+    /// Set the element used by a hero's skill
+    pub fn set_skill_element(ctx: Context<SetSkillElement>, element: u8) -> Result<()> {
+        instructions::set_skill_element(ctx, element)
+    }
+
     /// Create a new enemy template (admin only)
+    #[allow(clippy::too_many_arguments)]
     pub fn create_enemy_template(
         ctx: Context<CreateEnemyTemplate>,
         id: u32,
@@ -91,6 +123,8 @@ pub mod rpg_program {
         luck: u16,
         ai_flags: u8,
         xp_reward: u32,
+        element: u8,
+        resistances: [i16; constants::ELEMENT_COUNT],
     ) -> Result<()> {
         instructions::create_enemy_template(
             ctx,
@@ -107,21 +141,209 @@ pub mod rpg_program {
             luck,
             ai_flags,
             xp_reward,
+            element,
+            resistances,
         )
     }
 
+    /// Attach a loot table to an enemy template (admin only)
+    pub fn create_loot_table(ctx: Context<CreateLootTable>, entries: Vec<state::LootEntry>) -> Result<()> {
+        instructions::create_loot_table(ctx, entries)
+    }
+
     /// Initialize the treasury (admin only)
     pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
         instructions::initialize_treasury(ctx)
     }
 
-    /// Cancel a pending roll that has timed out
+    /// Move fees out of the dev bucket, keeping the reserve it holds back (treasury authority only)
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        instructions::withdraw_treasury(ctx, amount)
+    }
+
+    /// Set the lamports kept for refunds, pending escrow counts towards them (treasury authority only)
+    pub fn set_treasury_reserve(ctx: Context<SetTreasuryReserve>, reserve: u64) -> Result<()> {
+        instructions::set_treasury_reserve(ctx, reserve)
+    }
+
+    /// Move fees held by the treasury account into the vault PDA (treasury authority only)
+    pub fn migrate_treasury_vault(ctx: Context<MigrateTreasuryVault>) -> Result<()> {
+        instructions::migrate_treasury_vault(ctx)
+    }
+
+    /// Grow a player created before gold and referrals to the current layout (player only)
+    pub fn migrate_player(ctx: Context<MigratePlayer>) -> Result<()> {
+        instructions::migrate_player(ctx)
+    }
+
+    /// Grow a hero created before classes and elements to the current layout as a Warrior (owner only)
+    pub fn migrate_hero(ctx: Context<MigrateHero>) -> Result<()> {
+        instructions::migrate_hero(ctx)
+    }
+
+    /// Grow an enemy template created before elements to the current layout as a neutral enemy
+    pub fn migrate_enemy_template(ctx: Context<MigrateEnemyTemplate>) -> Result<()> {
+        instructions::migrate_enemy_template(ctx)
+    }
+
+    /// Close a battle left in the pre-migration layout and refund its rent (owner only)
+    pub fn close_legacy_battle(ctx: Context<CloseLegacyBattle>) -> Result<()> {
+        instructions::close_legacy_battle(ctx)
+    }
+
+    /// Close a roll session left in the pre-migration layout and refund its rent (owner only)
+    pub fn close_legacy_roll_session(ctx: Context<CloseLegacyRollSession>) -> Result<()> {
+        instructions::close_legacy_roll_session(ctx)
+    }
+
+    /// Create the treasury token account for a payment mint (treasury authority only)
+    pub fn create_treasury_token_account(ctx: Context<CreateTreasuryTokenAccount>) -> Result<()> {
+        instructions::create_treasury_token_account(ctx)
+    }
+
+    /// Move collected tokens out of a treasury token account (treasury authority only)
+    pub fn withdraw_treasury_tokens(ctx: Context<WithdrawTreasuryTokens>, amount: u64) -> Result<()> {
+        instructions::withdraw_treasury_tokens(ctx, amount)
+    }
+
+    /// Accept an SPL token at configured prices, or SOL only with None (admin only)
+    pub fn set_token_payment(
+        ctx: Context<SetTokenPayment>,
+        token_payment: Option<state::TokenPaymentConfig>,
+    ) -> Result<()> {
+        instructions::set_token_payment(ctx, token_payment)
+    }
+
+    /// Set basis-point shares and recipients of the revenue buckets (treasury authority only)
+    pub fn set_revenue_split(
+        ctx: Context<SetRevenueSplit>,
+        shares: [u16; constants::REVENUE_BUCKET_COUNT],
+        recipients: [Pubkey; constants::REVENUE_BUCKET_COUNT],
+    ) -> Result<()> {
+        instructions::set_revenue_split(ctx, shares, recipients)
+    }
+
+    /// Claim a revenue bucket's balance (bucket recipient only)
+    pub fn claim_revenue(ctx: Context<ClaimRevenue>, bucket: u8) -> Result<()> {
+        instructions::claim_revenue(ctx, bucket)
+    }
+
+    /// Claim referral rewards earned from referees' SOL spending
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        instructions::claim_referral_rewards(ctx)
+    }
+
+    /// Initialize the game config with default tuning (treasury authority only)
+    pub fn initialize_game_config(ctx: Context<InitializeGameConfig>) -> Result<()> {
+        instructions::initialize_game_config(ctx)
+    }
+
+    /// Update combat and economy tuning (admin only)
+    pub fn update_game_config(
+        ctx: Context<UpdateGameConfig>,
+        combat: state::CombatConfig,
+        economy: state::EconomyConfig,
+    ) -> Result<()> {
+        instructions::update_game_config(ctx, combat, economy)
+    }
+
+    /// Choose between slot hashes and an oracle program, with its request layout, for randomness (admin only)
+    pub fn set_randomness_provider(
+        ctx: Context<UpdateGameConfig>,
+        provider: u8,
+        oracle_program: Pubkey,
+        oracle_layout: state::OracleLayout,
+    ) -> Result<()> {
+        instructions::set_randomness_provider(ctx, provider, oracle_program, oracle_layout)
+    }
+
+    /// Publish a roll odds table (admin only)
+    pub fn create_odds_table(
+        ctx: Context<CreateOddsTable>,
+        id: u32,
+        weights: [u16; constants::RARITY_COUNT],
+        xp_rewards: [u32; constants::RARITY_COUNT],
+    ) -> Result<()> {
+        instructions::create_odds_table(ctx, id, weights, xp_rewards)
+    }
+
+    /// Change the weights and rewards of an odds table, paid sessions keep their snapshot (admin only)
+    pub fn update_odds_table(
+        ctx: Context<UpdateOddsTable>,
+        weights: [u16; constants::RARITY_COUNT],
+        xp_rewards: [u32; constants::RARITY_COUNT],
+    ) -> Result<()> {
+        instructions::update_odds_table(ctx, weights, xp_rewards)
+    }
+
+    /// Create a time-limited roll banner with featured rewards (admin only)
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_banner(
+        ctx: Context<CreateBanner>,
+        id: u32,
+        name: [u8; 32],
+        start_time: i64,
+        end_time: i64,
+        roll_cost: u64,
+        roll_batch_cost: u64,
+        featured_min_rarity: u8,
+        featured_pool: Vec<state::LootEntry>,
+    ) -> Result<()> {
+        instructions::create_banner(
+            ctx,
+            id,
+            name,
+            start_time,
+            end_time,
+            roll_cost,
+            roll_batch_cost,
+            featured_min_rarity,
+            featured_pool,
+        )
+    }
+
+    /// Cancel a pending roll that has timed out, refunded only if its randomness never existed
     pub fn cancel_pending_roll(ctx: Context<CancelPendingRoll>) -> Result<()> {
         instructions::cancel_pending_roll(ctx)
     }
 
-    /// Cancel a pending battle that has timed out
+    /// Cancel a pending battle that has timed out, refunded only if its randomness never existed
     pub fn cancel_pending_battle(ctx: Context<CancelPendingBattle>) -> Result<()> {
         instructions::cancel_pending_battle(ctx)
     }
+
+    /// Move a pending roll's commit forward when its reveal slot was skipped
+    pub fn recommit_roll(ctx: Context<RecommitRoll>) -> Result<()> {
+        instructions::recommit_roll(ctx)
+    }
+
+    /// Move a pending battle's commit forward when its reveal slot was skipped
+    pub fn recommit_battle(ctx: Context<RecommitBattle>) -> Result<()> {
+        instructions::recommit_battle(ctx)
+    }
+
+    /// Move a per-turn battle's committed action forward when its reveal slot was skipped
+    pub fn recommit_battle_action(ctx: Context<RecommitBattleAction>) -> Result<()> {
+        instructions::recommit_battle_action(ctx)
+    }
+
+    /// Buy a common item for one of the hero's slots with gold
+    pub fn buy_item(ctx: Context<BuyItem>, item_type: u8) -> Result<()> {
+        instructions::buy_item(ctx, item_type)
+    }
+
+    /// Restore an item's durability with gold
+    pub fn repair_item(ctx: Context<ImproveItem>) -> Result<()> {
+        instructions::repair_item(ctx)
+    }
+
+    /// Raise an item's rarity by one with gold
+    pub fn upgrade_item(ctx: Context<ImproveItem>) -> Result<()> {
+        instructions::upgrade_item(ctx)
+    }
+
+    /// Equip an item owned by the hero in its slot
+    pub fn equip_item(ctx: Context<EquipItem>) -> Result<()> {
+        instructions::equip_item(ctx)
+    }
 }
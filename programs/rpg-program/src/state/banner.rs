@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::state::LootEntry;
+
+/// Time-limited roll event with its own price, odds table and featured rewards
+#[account]
+pub struct Banner {
+    pub id: u32,
+    pub name: [u8; 32],
+    pub start_time: i64, // Unix timestamp, inclusive
+    pub end_time: i64,   // Unix timestamp, exclusive
+    pub roll_cost: u64,  // Lamports
+    pub roll_batch_cost: u64, // Lamports for ROLL_BATCH_SIZE rolls
+    pub odds_table: Pubkey,
+    pub featured_min_rarity: u8, // Best roll of a session must reach this to win a featured item
+    pub featured_count: u8,
+    pub featured_pool: [LootEntry; MAX_LOOT_ENTRIES],
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Banner {
+    pub const LEN: usize = 8 + // discriminator
+        4 +  // id
+        32 + // name
+        8 +  // start_time
+        8 +  // end_time
+        8 +  // roll_cost
+        8 +  // roll_batch_cost
+        32 + // odds_table
+        1 +  // featured_min_rarity
+        1 +  // featured_count
+        (LootEntry::LEN * MAX_LOOT_ENTRIES) + // featured_pool
+        8 +  // created_at
+        1;   // bump
+
+    pub fn is_live(&self, now: i64) -> bool {
+        self.start_time <= now && now < self.end_time
+    }
+
+    pub fn active_featured(&self) -> &[LootEntry] {
+        let count = std::cmp::min(self.featured_count as usize, MAX_LOOT_ENTRIES);
+        &self.featured_pool[..count]
+    }
+
+    pub fn set_featured(&mut self, entries: &[LootEntry]) {
+        self.featured_pool = [LootEntry::default(); MAX_LOOT_ENTRIES];
+        self.featured_pool[..entries.len()].copy_from_slice(entries);
+        self.featured_count = entries.len() as u8;
+    }
+}
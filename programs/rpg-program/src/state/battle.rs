@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
+use solana_sha256_hasher::hashv;
 use crate::constants::*;
+use crate::state::{Attributes, CombatConfig, ElementalProfile, OracleLayout};
+use crate::utils::randomness::SessionOracle;
 
 #[account]
 pub struct Battle {
@@ -16,9 +19,54 @@ pub struct Battle {
     pub commit_slot: u64,
     pub created_at: i64,
     pub bump: u8,
+    // Fields below were appended after launch, legacy battles are closed by `close_legacy_battle`
+    pub hero_class: u8,
+    pub hero_attributes: Attributes,
+    pub enemy_attributes: Attributes,
+    pub hero_elemental: ElementalProfile,
+    pub enemy_elemental: ElementalProfile,
+    pub enemy_ai_flags: u8,
+    pub combat_config: CombatConfig,
+    pub hero_speed_gauge: u16,
+    pub enemy_speed_gauge: u16,
+    pub per_turn_randomness: bool,     // Each action is committed and resolved with a later slot hash
+    pub committed_action: Option<u8>,
+    pub action_commit_slot: u64,
+    pub turn_entropy: [u8; 32],        // Slot hash mixed into the current turn's draws
+    pub action_log: [u8; BATTLE_ACTION_LOG_LEN],
+    pub log_hash: [u8; 32],
+    pub recommit_count: u8,
+    pub randomness_provider: u8,
+    pub oracle_program: Pubkey,
+    pub secret_hash: [u8; 32], // Hash of the player's secret, see hash_player_secret
+    pub entry_cost: u64,       // Lamports or tokens paid into the treasury, refunded on cancel
+    pub payment_mint: Option<Pubkey>, // Mint paid in, None for SOL
+    pub referral_reward: u64,         // Credited to the player's referrer once resolved
+    pub settled: bool,
+    pub settled_at: i64,
+    pub oracle_layout: OracleLayout, // Request layout of oracle_program when the battle started
 }
 
-impl Battle {
+/// Battle layout before combat snapshots, escrow and oracles were added. Battles can't be carried
+/// over without those, so legacy ones are only closed
+#[derive(AnchorDeserialize)]
+pub struct LegacyBattle {
+    pub hero: Pubkey,
+    pub enemy_template: Pubkey,
+    pub hero_hp: u32,
+    pub enemy_hp: u32,
+    pub hero_max_hp: u32,
+    pub enemy_max_hp: u32,
+    pub rng_seed: [u8; 32],
+    pub turn: u8,
+    pub state: u8,
+    pub nonce: u64,
+    pub commit_slot: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl LegacyBattle {
     pub const LEN: usize = 8 + // discriminator
         32 + // hero
         32 + // enemy_template
@@ -34,6 +82,70 @@ impl Battle {
         8 +  // created_at
         1;   // bump
 
+    /// Read a legacy battle account, rejecting accounts in any other layout
+    pub fn from_account_data(data: &[u8]) -> Result<LegacyBattle> {
+        require!(
+            data.len() == LegacyBattle::LEN && data.starts_with(Battle::DISCRIMINATOR),
+            crate::errors::RpgError::InvalidLegacyLayout
+        );
+        LegacyBattle::deserialize(&mut &data[8..])
+            .map_err(|_| crate::errors::RpgError::InvalidLegacyLayout.into())
+    }
+}
+
+impl Battle {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // hero
+        32 + // enemy_template
+        4 +  // hero_hp
+        4 +  // enemy_hp
+        4 +  // hero_max_hp
+        4 +  // enemy_max_hp
+        32 + // rng_seed
+        1 +  // turn
+        1 +  // state
+        8 +  // nonce
+        8 +  // commit_slot
+        8 +  // created_at
+        1 +  // bump
+        1 +  // hero_class
+        (2 * 8) + // hero_attributes (8 u16s)
+        (2 * 8) + // enemy_attributes (8 u16s)
+        ElementalProfile::LEN + // hero_elemental
+        ElementalProfile::LEN + // enemy_elemental
+        1 +  // enemy_ai_flags
+        CombatConfig::LEN + // combat_config
+        2 +  // hero_speed_gauge
+        2 +  // enemy_speed_gauge
+        1 +  // per_turn_randomness
+        (1 + 1) + // committed_action (Option<u8>)
+        8 +  // action_commit_slot
+        32 + // turn_entropy
+        BATTLE_ACTION_LOG_LEN + // action_log
+        32 + // log_hash
+        1 +  // recommit_count
+        1 +  // randomness_provider
+        32 + // oracle_program
+        32 + // secret_hash
+        8 +  // entry_cost
+        (1 + 32) + // payment_mint (Option<Pubkey>)
+        8 +  // referral_reward
+        1 +  // settled
+        8 +  // settled_at
+        OracleLayout::LEN; // oracle_layout
+
+    /// Oracle the session was started with, reading `request` if one was passed
+    pub fn oracle<'a, 'info>(
+        &'a self,
+        request: Option<&'a AccountInfo<'info>>,
+    ) -> SessionOracle<'a, 'info> {
+        SessionOracle {
+            program: &self.oracle_program,
+            layout: &self.oracle_layout,
+            request,
+        }
+    }
+
     pub fn is_active(&self) -> bool {
         self.state == BATTLE_STATE_ACTIVE
     }
@@ -57,6 +169,10 @@ impl Battle {
         }
     }
 
+    pub fn heal_hero(&mut self, amount: u32) {
+        self.hero_hp = std::cmp::min(self.hero_max_hp, self.hero_hp.saturating_add(amount));
+    }
+
     pub fn damage_enemy(&mut self, damage: u32) {
         self.enemy_hp = self.enemy_hp.saturating_sub(damage);
         if self.enemy_hp == 0 {
@@ -64,6 +180,32 @@ impl Battle {
         }
     }
 
+    /// Add speed gauge gains, returning which side has a full gauge to spend on an extra action
+    pub fn fill_speed_gauges(&mut self, hero_gain: u16, enemy_gain: u16) -> (bool, bool) {
+        self.hero_speed_gauge = self.hero_speed_gauge.saturating_add(hero_gain);
+        self.enemy_speed_gauge = self.enemy_speed_gauge.saturating_add(enemy_gain);
+
+        let hero_extra = self.hero_speed_gauge >= SPEED_GAUGE_FULL;
+        if hero_extra {
+            self.hero_speed_gauge -= SPEED_GAUGE_FULL;
+        }
+        let enemy_extra = self.enemy_speed_gauge >= SPEED_GAUGE_FULL;
+        if enemy_extra {
+            self.enemy_speed_gauge -= SPEED_GAUGE_FULL;
+        }
+
+        (hero_extra, enemy_extra)
+    }
+
+    /// Seed for the current turn's draws, mixed with fresh entropy in per-turn mode
+    pub fn turn_seed(&self) -> [u8; 32] {
+        if self.per_turn_randomness {
+            hashv(&[&self.rng_seed, &self.turn_entropy]).to_bytes()
+        } else {
+            self.rng_seed
+        }
+    }
+
     pub fn next_turn(&mut self) {
         self.turn = self.turn.saturating_add(1);
     }
@@ -71,4 +213,229 @@ impl Battle {
     pub fn escape_battle(&mut self) {
         self.state = BATTLE_STATE_ESCAPED;
     }
+
+    /// Give up an active battle, it settles as a loss
+    pub fn forfeit(&mut self) {
+        self.state = BATTLE_STATE_HERO_LOST;
+        self.committed_action = None;
+    }
+
+    /// Move a committed action's reveal forward, keeping the action. Returns the old commit slot.
+    pub fn recommit_action(&mut self, slot: u64) -> Result<u64> {
+        require!(self.committed_action.is_some(), crate::errors::RpgError::ActionNotCommitted);
+
+        let previous_commit_slot = self.action_commit_slot;
+        self.action_commit_slot = slot;
+        self.recommit_count = self.recommit_count.saturating_add(1);
+        Ok(previous_commit_slot)
+    }
+
+    /// Append the current turn to the action log and rolling hash.
+    /// Must be called before `next_turn`.
+    pub fn record_turn(&mut self, action: u8, hero_damage: u32, enemy_damage: u32) {
+        let index = (self.turn / 4) as usize;
+        let shift = (self.turn % 4) * 2;
+        self.action_log[index] &= !(0b11 << shift);
+        self.action_log[index] |= (action & 0b11) << shift;
+
+        self.log_hash = hashv(&[
+            &self.log_hash,
+            &[self.turn, action],
+            &hero_damage.to_le_bytes(),
+            &enemy_damage.to_le_bytes(),
+            &self.hero_hp.to_le_bytes(),
+            &self.enemy_hp.to_le_bytes(),
+            &[self.state],
+            &self.turn_entropy,
+        ])
+        .to_bytes();
+    }
+
+    /// Action taken by the hero on a given turn
+    pub fn logged_action(&self, turn: u8) -> u8 {
+        (self.action_log[(turn / 4) as usize] >> ((turn % 4) * 2)) & 0b11
+    }
+
+    /// Every hero action taken so far, in turn order
+    pub fn logged_actions(&self) -> Vec<u8> {
+        (0..self.turn).map(|turn| self.logged_action(turn)).collect()
+    }
+
+    pub fn mark_settled(&mut self, timestamp: i64) -> Result<()> {
+        require!(!self.is_active(), crate::errors::RpgError::InvalidBattleAction);
+        require!(!self.settled, crate::errors::RpgError::BattleAlreadySettled);
+
+        self.settled = true;
+        self.settled_at = timestamp;
+        Ok(())
+    }
+
+    pub fn can_be_cleaned_up(&self, now: i64) -> bool {
+        self.settled && now >= self.settled_at.saturating_add(BATTLE_CLEANUP_DELAY_SECONDS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished_battle() -> Battle {
+        Battle {
+            hero: Pubkey::default(),
+            enemy_template: Pubkey::default(),
+            hero_hp: 50,
+            enemy_hp: 0,
+            hero_max_hp: 100,
+            enemy_max_hp: 100,
+            rng_seed: [0u8; 32],
+            turn: 3,
+            state: BATTLE_STATE_HERO_WON,
+            nonce: 0,
+            commit_slot: 0,
+            created_at: 0,
+            bump: 0,
+            hero_class: HERO_CLASS_WARRIOR,
+            hero_attributes: Attributes::new_base(),
+            enemy_attributes: Attributes::new_base(),
+            hero_elemental: ElementalProfile::default(),
+            enemy_elemental: ElementalProfile::default(),
+            enemy_ai_flags: AI_AGGRESSIVE,
+            combat_config: CombatConfig::default(),
+            hero_speed_gauge: 0,
+            enemy_speed_gauge: 0,
+            per_turn_randomness: false,
+            committed_action: None,
+            action_commit_slot: 0,
+            turn_entropy: [0u8; 32],
+            action_log: [0u8; BATTLE_ACTION_LOG_LEN],
+            log_hash: [0u8; 32],
+            recommit_count: 0,
+            randomness_provider: RANDOMNESS_PROVIDER_SLOT_HASHES,
+            oracle_program: Pubkey::default(),
+            secret_hash: [0u8; 32],
+            entry_cost: 0,
+            payment_mint: None,
+            referral_reward: 0,
+            settled: false,
+            settled_at: 0,
+            oracle_layout: OracleLayout::default(),
+        }
+    }
+
+    #[test]
+    fn test_read_legacy_layout() {
+        let hero = Pubkey::new_unique();
+        let mut legacy = Battle::DISCRIMINATOR.to_vec();
+        legacy.extend_from_slice(hero.as_ref());
+        legacy.extend_from_slice(Pubkey::new_unique().as_ref());
+        for hp in [40u32, 0, 100, 80] {
+            legacy.extend_from_slice(&hp.to_le_bytes());
+        }
+        legacy.extend_from_slice(&[7u8; 32]);
+        legacy.extend_from_slice(&[2, BATTLE_STATE_HERO_WON]);
+        legacy.extend_from_slice(&11u64.to_le_bytes());
+        legacy.extend_from_slice(&500u64.to_le_bytes());
+        legacy.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        legacy.push(254);
+        assert_eq!(legacy.len(), LegacyBattle::LEN);
+
+        let battle = LegacyBattle::from_account_data(&legacy).unwrap();
+        assert_eq!(battle.hero, hero);
+        assert_eq!(battle.hero_hp, 40);
+        assert_eq!(battle.turn, 2);
+        assert_eq!(battle.state, BATTLE_STATE_HERO_WON);
+        assert_eq!(battle.nonce, 11);
+        assert_eq!(battle.commit_slot, 500);
+        assert_eq!(battle.bump, 254);
+
+        // The legacy fields keep their offsets in the current layout
+        let mut current = finished_battle();
+        current.hero = hero;
+        let mut data = Vec::new();
+        current.try_serialize(&mut data).unwrap();
+        assert_eq!(data[8..40], legacy[8..40]);
+
+        // Current battles can't be read as legacy ones
+        data.resize(Battle::LEN, 0);
+        assert!(LegacyBattle::from_account_data(&data).is_err());
+        legacy[0] ^= 1;
+        assert!(LegacyBattle::from_account_data(&legacy).is_err());
+    }
+
+    #[test]
+    fn test_mark_settled_only_once() {
+        let mut battle = finished_battle();
+
+        assert!(battle.mark_settled(100).is_ok());
+        assert!(battle.settled);
+        assert_eq!(battle.settled_at, 100);
+
+        // Settling the same battle again must fail
+        assert!(battle.mark_settled(200).is_err());
+        assert_eq!(battle.settled_at, 100);
+    }
+
+    #[test]
+    fn test_mark_settled_requires_finished_battle() {
+        let mut battle = finished_battle();
+        battle.state = BATTLE_STATE_ACTIVE;
+
+        assert!(battle.mark_settled(100).is_err());
+        assert!(!battle.settled);
+    }
+
+    #[test]
+    fn test_action_log_packing() {
+        let mut battle = finished_battle();
+        battle.turn = 0;
+        let actions = [ACTION_ATTACK, ACTION_ESCAPE, ACTION_SKILL, ACTION_DEFEND, ACTION_ESCAPE];
+
+        for action in actions {
+            let previous_hash = battle.log_hash;
+            battle.record_turn(action, 1, 2);
+            assert_ne!(battle.log_hash, previous_hash);
+            battle.next_turn();
+        }
+
+        assert_eq!(battle.logged_actions(), actions.to_vec());
+    }
+
+    #[test]
+    fn test_recommit_action() {
+        let mut battle = finished_battle();
+        battle.state = BATTLE_STATE_ACTIVE;
+        battle.per_turn_randomness = true;
+
+        // Nothing committed, nothing to move
+        assert!(battle.recommit_action(500).is_err());
+
+        battle.committed_action = Some(ACTION_SKILL);
+        battle.action_commit_slot = 10;
+        assert_eq!(battle.recommit_action(700).unwrap(), 10);
+        assert_eq!(battle.committed_action, Some(ACTION_SKILL));
+        assert_eq!(battle.action_commit_slot, 700);
+        assert_eq!(battle.recommit_count, 1);
+    }
+
+    #[test]
+    fn test_forfeit_settles_as_loss() {
+        let mut battle = finished_battle();
+        battle.state = BATTLE_STATE_ACTIVE;
+        battle.committed_action = Some(ACTION_ATTACK);
+
+        battle.forfeit();
+        assert_eq!(battle.state, BATTLE_STATE_HERO_LOST);
+        assert_eq!(battle.committed_action, None);
+        assert!(battle.mark_settled(100).is_ok());
+    }
+
+    #[test]
+    fn test_can_be_cleaned_up() {
+        let mut battle = finished_battle();
+        assert!(!battle.can_be_cleaned_up(i64::MAX));
+
+        battle.mark_settled(100).unwrap();
+        assert!(!battle.can_be_cleaned_up(100));
+        assert!(battle.can_be_cleaned_up(100 + BATTLE_CLEANUP_DELAY_SECONDS));
+    }
 }
\ No newline at end of file
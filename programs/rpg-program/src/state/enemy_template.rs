@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use crate::state::Attributes;
+use crate::state::{Attributes, CombatConfig, ElementalProfile};
 
 #[account]
 pub struct EnemyTemplate {
@@ -11,9 +11,24 @@ pub struct EnemyTemplate {
     pub xp_reward: u32,
     pub created_at: i64,
     pub bump: u8,
+    // Fields below were appended after launch, see `EnemyTemplate::from_legacy`
+    pub elemental: ElementalProfile,
 }
 
-impl EnemyTemplate {
+/// Enemy template layout before elements were added
+#[derive(AnchorDeserialize)]
+pub struct LegacyEnemyTemplate {
+    pub id: u32,
+    pub name: [u8; 32],
+    pub level: u16,
+    pub base_attributes: Attributes,
+    pub ai_flags: u8,
+    pub xp_reward: u32,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl LegacyEnemyTemplate {
     pub const LEN: usize = 8 + // discriminator
         4 +  // id
         32 + // name
@@ -23,9 +38,44 @@ impl EnemyTemplate {
         4 +  // xp_reward
         8 +  // created_at
         1;   // bump
+}
 
-    pub fn calculate_hp(&self) -> u32 {
-        (self.base_attributes.vitality as u32) * (crate::constants::BASE_HP_MULTIPLIER as u32)
+impl EnemyTemplate {
+    pub const LEN: usize = 8 + // discriminator
+        4 +  // id
+        32 + // name
+        2 +  // level
+        (2 * 8) + // base_attributes (8 u16s)
+        1 +  // ai_flags
+        4 +  // xp_reward
+        8 +  // created_at
+        1 +  // bump
+        ElementalProfile::LEN; // elemental
+
+    /// Rebuild a legacy enemy template in the current layout as a neutral enemy without resistances
+    pub fn from_legacy(data: &[u8]) -> Result<EnemyTemplate> {
+        require!(
+            data.len() == LegacyEnemyTemplate::LEN && data.starts_with(EnemyTemplate::DISCRIMINATOR),
+            crate::errors::RpgError::InvalidLegacyLayout
+        );
+        let legacy = LegacyEnemyTemplate::deserialize(&mut &data[8..])
+            .map_err(|_| crate::errors::RpgError::InvalidLegacyLayout)?;
+
+        Ok(EnemyTemplate {
+            id: legacy.id,
+            name: legacy.name,
+            level: legacy.level,
+            base_attributes: legacy.base_attributes,
+            ai_flags: legacy.ai_flags,
+            xp_reward: legacy.xp_reward,
+            created_at: legacy.created_at,
+            bump: legacy.bump,
+            elemental: ElementalProfile::default(),
+        })
+    }
+
+    pub fn calculate_hp(&self, config: &CombatConfig) -> u32 {
+        crate::utils::math::calculate_hp(config, self.base_attributes.vitality)
     }
 
     pub fn get_name_string(&self) -> String {
@@ -45,4 +95,58 @@ impl EnemyTemplate {
     pub fn is_balanced(&self) -> bool {
         self.ai_flags & crate::constants::AI_BALANCED != 0
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::ELEMENT_NEUTRAL;
+    use crate::errors::RpgError;
+
+    #[test]
+    fn test_migrate_legacy_layout() {
+        let mut name = [0u8; 32];
+        name[..6].copy_from_slice(b"Goblin");
+        let mut legacy = EnemyTemplate::DISCRIMINATOR.to_vec();
+        legacy.extend_from_slice(&9u32.to_le_bytes());
+        legacy.extend_from_slice(&name);
+        legacy.extend_from_slice(&4u16.to_le_bytes());
+        for value in [8u16, 7, 6, 5, 4, 3, 2, 1] {
+            legacy.extend_from_slice(&value.to_le_bytes());
+        }
+        legacy.push(crate::constants::AI_AGGRESSIVE);
+        legacy.extend_from_slice(&75u32.to_le_bytes());
+        legacy.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        legacy.push(254);
+        assert_eq!(legacy.len(), LegacyEnemyTemplate::LEN);
+
+        let template = EnemyTemplate::from_legacy(&legacy).unwrap();
+        assert_eq!(template.id, 9);
+        assert_eq!(template.get_name_string(), "Goblin");
+        assert_eq!(template.level, 4);
+        assert_eq!(template.base_attributes.vitality, 6);
+        assert!(template.is_aggressive());
+        assert_eq!(template.xp_reward, 75);
+        assert_eq!(template.created_at, 1_700_000_000);
+        assert_eq!(template.bump, 254);
+        assert_eq!(template.elemental.element, ELEMENT_NEUTRAL);
+        assert_eq!(template.elemental.resistances, [0; crate::constants::ELEMENT_COUNT]);
+
+        // The legacy fields keep their offsets and the rebuilt account fills the new size
+        let mut data = Vec::new();
+        template.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), EnemyTemplate::LEN);
+        assert_eq!(data[..LegacyEnemyTemplate::LEN], legacy[..]);
+
+        // Already migrated or foreign accounts are rejected
+        assert!(matches!(
+            EnemyTemplate::from_legacy(&data),
+            Err(error) if error == RpgError::InvalidLegacyLayout.into()
+        ));
+        legacy[0] ^= 1;
+        assert!(matches!(
+            EnemyTemplate::from_legacy(&legacy),
+            Err(error) if error == RpgError::InvalidLegacyLayout.into()
+        ));
+    }
+}
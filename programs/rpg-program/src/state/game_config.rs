@@ -0,0 +1,186 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+
+/// Combat tuning, snapshotted into every battle so replays stay deterministic
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CombatConfig {
+    pub base_hp_multiplier: u16,      // HP = vitality * multiplier
+    pub base_damage_reduction: u16,   // damage = max(1, damage - defense/reduction)
+    pub critical_multiplier: u16,
+    pub base_escape_chance: u16,      // Out of 10000
+    pub agility_escape_bonus: u16,    // Per agility point difference, out of 10000
+    pub min_escape_chance: u16,       // Out of 10000
+    pub max_escape_chance: u16,       // Out of 10000
+    pub element_advantage_multiplier: u16,    // Out of 10000
+    pub element_disadvantage_multiplier: u16, // Out of 10000
+    pub min_speed_advantage: u16,     // Dexterity surplus needed to fill the speed gauge, out of 10000
+}
+
+impl CombatConfig {
+    pub const LEN: usize = 2 * 10; // 10 u16s
+}
+
+impl Default for CombatConfig {
+    fn default() -> Self {
+        Self {
+            base_hp_multiplier: BASE_HP_MULTIPLIER,
+            base_damage_reduction: BASE_DAMAGE_REDUCTION,
+            critical_multiplier: CRITICAL_MULTIPLIER,
+            base_escape_chance: BASE_ESCAPE_CHANCE,
+            agility_escape_bonus: AGILITY_ESCAPE_BONUS,
+            min_escape_chance: MIN_ESCAPE_CHANCE,
+            max_escape_chance: MAX_ESCAPE_CHANCE,
+            element_advantage_multiplier: ELEMENT_ADVANTAGE_MULTIPLIER,
+            element_disadvantage_multiplier: ELEMENT_DISADVANTAGE_MULTIPLIER,
+            min_speed_advantage: MIN_SPEED_ADVANTAGE,
+        }
+    }
+}
+
+/// Prices, rewards and progression tuning
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EconomyConfig {
+    pub hero_purchase_cost: u64, // Lamports
+    pub roll_cost: u64,          // Lamports
+    pub roll_batch_cost: u64,    // Lamports for ROLL_BATCH_SIZE rolls
+    pub battle_entry_cost: u64,  // Lamports
+    pub xp_per_level_base: u32,
+    pub attribute_points_per_level: u16,
+    pub max_level: u16,
+    pub max_heroes_per_player: u8,
+    pub luck_half_point: u16,       // Luck giving half of max_luck_bonus
+    pub max_luck_bonus: u16,        // Out of 10000
+    pub pity_hard_limit: u16,
+    pub pity_soft_start: u16,
+    pub pity_soft_bonus: u16,       // Per miss past the soft start, out of 10000
+    pub gold_per_enemy_level: u32,  // Gold awarded per enemy level on victory
+    pub item_shop_cost: u64,        // Gold
+    pub item_repair_cost: u32,      // Gold per durability point
+    pub item_upgrade_cost: u64,     // Gold per rarity step, times the rarity reached
+    pub referral_bps: u16,          // Referrer's share of referee SOL spending, out of 10000
+    pub element_change_cost: u64,   // Gold to change a hero's skill element
+}
+
+impl EconomyConfig {
+    pub const LEN: usize = 8 + // hero_purchase_cost
+        8 +  // roll_cost
+        8 +  // roll_batch_cost
+        8 +  // battle_entry_cost
+        4 +  // xp_per_level_base
+        2 +  // attribute_points_per_level
+        2 +  // max_level
+        1 +  // max_heroes_per_player
+        2 +  // luck_half_point
+        2 +  // max_luck_bonus
+        2 +  // pity_hard_limit
+        2 +  // pity_soft_start
+        2 +  // pity_soft_bonus
+        4 +  // gold_per_enemy_level
+        8 +  // item_shop_cost
+        4 +  // item_repair_cost
+        8 +  // item_upgrade_cost
+        2 +  // referral_bps
+        8;   // element_change_cost
+}
+
+impl Default for EconomyConfig {
+    fn default() -> Self {
+        Self {
+            hero_purchase_cost: HERO_PURCHASE_COST,
+            roll_cost: ROLL_COST,
+            roll_batch_cost: ROLL_BATCH_COST,
+            battle_entry_cost: BATTLE_ENTRY_COST,
+            xp_per_level_base: XP_PER_LEVEL_BASE,
+            attribute_points_per_level: ATTRIBUTE_POINTS_PER_LEVEL,
+            max_level: MAX_LEVEL,
+            max_heroes_per_player: MAX_HEROES_PER_PLAYER,
+            luck_half_point: LUCK_HALF_POINT,
+            max_luck_bonus: MAX_LUCK_BONUS,
+            pity_hard_limit: PITY_HARD_LIMIT,
+            pity_soft_start: PITY_SOFT_START,
+            pity_soft_bonus: PITY_SOFT_BONUS,
+            gold_per_enemy_level: GOLD_PER_ENEMY_LEVEL,
+            item_shop_cost: ITEM_SHOP_COST,
+            item_repair_cost: ITEM_REPAIR_COST,
+            item_upgrade_cost: ITEM_UPGRADE_COST,
+            referral_bps: REFERRAL_BPS,
+            element_change_cost: ELEMENT_CHANGE_COST,
+        }
+    }
+}
+
+/// Prices when paying with the configured SPL token instead of SOL (in token base units)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenPaymentConfig {
+    pub mint: Pubkey,
+    pub hero_purchase_cost: u64,
+    pub roll_cost: u64, // Standard rolls only, banners are paid in SOL
+    pub roll_batch_cost: u64,
+    pub battle_entry_cost: u64,
+}
+
+impl TokenPaymentConfig {
+    pub const LEN: usize = 32 + // mint
+        8 +  // hero_purchase_cost
+        8 +  // roll_cost
+        8 +  // roll_batch_cost
+        8;   // battle_entry_cost
+}
+
+#[account]
+pub struct GameConfig {
+    pub authority: Pubkey,
+    pub combat: CombatConfig,
+    pub economy: EconomyConfig,
+    pub randomness_provider: u8,
+    pub oracle_program: Pubkey, // Owner of oracle request accounts
+    pub token_payment: Option<TokenPaymentConfig>, // None accepts SOL only
+    pub updated_at: i64,
+    pub bump: u8,
+    pub oracle_layout: OracleLayout, // Where oracle_program keeps a request's fields
+}
+
+impl GameConfig {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // authority
+        CombatConfig::LEN + // combat
+        EconomyConfig::LEN + // economy
+        1 +  // randomness_provider
+        32 + // oracle_program
+        (1 + TokenPaymentConfig::LEN) + // token_payment (Option<TokenPaymentConfig>)
+        8 +  // updated_at
+        1 +  // bump
+        OracleLayout::LEN; // oracle_layout
+}
+
+/// Where an oracle program's request account keeps each field, as byte offsets from the
+/// start of the account data, so any oracle with a fixed layout can be plugged in
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OracleLayout {
+    pub discriminator: [u8; ORACLE_DISCRIMINATOR_LEN], // Leading bytes of every request account
+    pub requester_offset: u16,    // Pubkey of the roll session or battle
+    pub request_slot_offset: u16, // u64 slot the request was made in
+    pub fulfilled_offset: u16,    // bool
+    pub randomness_offset: u16,   // [u8; 32]
+}
+
+impl OracleLayout {
+    pub const LEN: usize = ORACLE_DISCRIMINATOR_LEN + // discriminator
+        2 +  // requester_offset
+        2 +  // request_slot_offset
+        2 +  // fulfilled_offset
+        2;   // randomness_offset
+}
+
+/// The mock oracle's `RandomnessRequest` layout
+impl Default for OracleLayout {
+    fn default() -> Self {
+        Self {
+            discriminator: MOCK_ORACLE_REQUEST_DISCRIMINATOR,
+            requester_offset: 8,
+            request_slot_offset: 40,
+            fulfilled_offset: 48,
+            randomness_offset: 49,
+        }
+    }
+}
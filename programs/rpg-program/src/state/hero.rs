@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use crate::state::Attributes;
+use crate::state::{Attributes, EconomyConfig, ElementalProfile, Item};
 use crate::constants::*;
 
 #[account]
@@ -17,9 +17,32 @@ pub struct Hero {
     pub total_victories: u32,
     pub created_at: i64,
     pub bump: u8,
+    // Fields below were appended after launch, see `Hero::from_legacy`
+    pub hero_class: u8,
+    pub elemental: ElementalProfile, // element is the hero's skill element, resistances come from gear
+    pub roll_pity: u16,  // Consecutive rolls below PITY_RARITY_FLOOR
+    pub item_count: u32, // Items created for this hero, the next one's PDA seed
 }
 
-impl Hero {
+/// Hero layout before classes, elements, pity and the item counter were added
+#[derive(AnchorDeserialize)]
+pub struct LegacyHero {
+    pub owner: Pubkey,
+    pub player: Pubkey,
+    pub index: u8,
+    pub level: u16,
+    pub xp: u32,
+    pub base_attributes: Attributes,
+    pub equipped_weapon: Option<Pubkey>,
+    pub equipped_armor: Option<Pubkey>,
+    pub equipped_accessory: Option<Pubkey>,
+    pub total_battles: u32,
+    pub total_victories: u32,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl LegacyHero {
     pub const LEN: usize = 8 + // discriminator
         32 + // owner
         32 + // player
@@ -34,44 +57,111 @@ impl Hero {
         4 +  // total_victories
         8 +  // created_at
         1;   // bump
+}
+
+impl Hero {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // owner
+        32 + // player
+        1 +  // index
+        2 +  // level
+        4 +  // xp
+        (2 * 8) + // base_attributes (8 u16s)
+        (1 + 32) + // equipped_weapon (Option<Pubkey>)
+        (1 + 32) + // equipped_armor (Option<Pubkey>)
+        (1 + 32) + // equipped_accessory (Option<Pubkey>)
+        4 +  // total_battles
+        4 +  // total_victories
+        8 +  // created_at
+        1 +  // bump
+        1 +  // hero_class
+        ElementalProfile::LEN + // elemental
+        2 +  // roll_pity
+        4;   // item_count
 
-    pub fn xp_required_for_next_level(&self) -> u32 {
-        XP_PER_LEVEL_BASE * (self.level as u32 + 1)
+    /// Rebuild a legacy hero in the current layout, classless heroes become Warriors
+    pub fn from_legacy(data: &[u8]) -> Result<Hero> {
+        require!(
+            data.len() == LegacyHero::LEN && data.starts_with(Hero::DISCRIMINATOR),
+            crate::errors::RpgError::InvalidLegacyLayout
+        );
+        let legacy = LegacyHero::deserialize(&mut &data[8..])
+            .map_err(|_| crate::errors::RpgError::InvalidLegacyLayout)?;
+
+        Ok(Hero {
+            owner: legacy.owner,
+            player: legacy.player,
+            index: legacy.index,
+            level: legacy.level,
+            xp: legacy.xp,
+            base_attributes: legacy.base_attributes,
+            equipped_weapon: legacy.equipped_weapon,
+            equipped_armor: legacy.equipped_armor,
+            equipped_accessory: legacy.equipped_accessory,
+            total_battles: legacy.total_battles,
+            total_victories: legacy.total_victories,
+            created_at: legacy.created_at,
+            bump: legacy.bump,
+            hero_class: HERO_CLASS_WARRIOR,
+            elemental: ElementalProfile {
+                element: CLASS_SKILL_ELEMENTS[HERO_CLASS_WARRIOR as usize],
+                ..ElementalProfile::default()
+            },
+            roll_pity: 0,
+            item_count: 0,
+        })
+    }
+
+    pub fn xp_required_for_next_level(&self, config: &EconomyConfig) -> u32 {
+        crate::utils::math::calculate_xp_required(config, self.level)
     }
 
-    pub fn can_level_up(&self) -> bool {
-        self.level < MAX_LEVEL && self.xp >= self.xp_required_for_next_level()
+    pub fn can_level_up(&self, config: &EconomyConfig) -> bool {
+        self.level < config.max_level && self.xp >= self.xp_required_for_next_level(config)
     }
 
-    pub fn level_up(&mut self) -> Result<u16> {
-        if !self.can_level_up() {
+    pub fn level_up(&mut self, config: &EconomyConfig) -> Result<u16> {
+        if !self.can_level_up(config) {
             return Err(crate::errors::RpgError::InsufficientXp.into());
         }
 
-        self.xp = self.xp.saturating_sub(self.xp_required_for_next_level());
+        self.xp = self.xp.saturating_sub(self.xp_required_for_next_level(config));
         self.level = self.level.saturating_add(1);
         
-        Ok(ATTRIBUTE_POINTS_PER_LEVEL)
+        Ok(config.attribute_points_per_level)
+    }
+
+    /// Take the index seeding the hero's next item PDA, never reused even when nonces are
+    pub fn next_item_index(&mut self) -> u32 {
+        let index = self.item_count;
+        self.item_count = self.item_count.saturating_add(1);
+        index
     }
 
     pub fn add_xp(&mut self, amount: u32) {
         self.xp = self.xp.saturating_add(amount);
     }
 
-    pub fn calculate_hp(&self) -> u32 {
-        (self.base_attributes.vitality as u32) * (BASE_HP_MULTIPLIER as u32)
+    /// Base attributes plus the durability-scaled bonuses of the hero's equipped items
+    pub fn calculate_total_attributes(&self, equipped: &[&Item]) -> Attributes {
+        let mut total = self.base_attributes;
+        for item in equipped {
+            total.add(&item.effective_bonus());
+        }
+        total
     }
 
-    pub fn calculate_total_attributes(&self) -> Attributes {
-        // Start with base attributes
-        let total = self.base_attributes;
-        
-        // TODO: Add equipment bonuses here when items are implemented
-        // if let Some(weapon) = self.equipped_weapon { ... }
-        // if let Some(armor) = self.equipped_armor { ... }
-        // if let Some(accessory) = self.equipped_accessory { ... }
-        
-        total
+    /// Skill element plus the resistances of the hero's equipped elemental items, capped
+    pub fn calculate_elemental(&self, equipped: &[&Item]) -> ElementalProfile {
+        let mut elemental = self.elemental;
+        for item in equipped {
+            if let Some(resistance) = elemental.resistances.get_mut(item.element as usize) {
+                *resistance = resistance
+                    .saturating_add(item.effective_resistance())
+                    .min(MAX_ELEMENT_RESISTANCE);
+            }
+        }
+        elemental
     }
 
     pub fn add_battle_stats(&mut self, victory: bool) {
@@ -118,4 +208,132 @@ impl Hero {
         };
         Ok(removed)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hero() -> Hero {
+        Hero {
+            owner: Pubkey::new_unique(),
+            player: Pubkey::new_unique(),
+            index: 0,
+            level: 1,
+            xp: 0,
+            base_attributes: Attributes::new_for_class(HERO_CLASS_WARRIOR),
+            equipped_weapon: None,
+            equipped_armor: None,
+            equipped_accessory: None,
+            total_battles: 0,
+            total_victories: 0,
+            created_at: 0,
+            bump: 0,
+            hero_class: HERO_CLASS_WARRIOR,
+            elemental: ElementalProfile::default(),
+            roll_pity: 0,
+            item_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_equipment_changes_combat_attributes() {
+        let hero = hero();
+        let base = hero.base_attributes.strength;
+        let mut weapon = Item::generate(Pubkey::new_unique(), ITEM_TYPE_WEAPON, RARITY_RARE, ELEMENT_NEUTRAL, 0);
+        assert_eq!(hero.calculate_total_attributes(&[]).strength, base);
+        assert_eq!(hero.calculate_total_attributes(&[&weapon]).strength, base + 4);
+
+        // Broken gear does nothing until it is repaired
+        weapon.wear(u16::MAX);
+        assert_eq!(hero.calculate_total_attributes(&[&weapon]).strength, base);
+        weapon.repair().unwrap();
+        assert_eq!(hero.calculate_total_attributes(&[&weapon]).strength, base + 4);
+
+        // Upgrades raise the bonus to the next rarity's
+        weapon.upgrade().unwrap();
+        assert_eq!(hero.calculate_total_attributes(&[&weapon]).strength, base + 7);
+    }
+
+    #[test]
+    fn test_equipment_resistances() {
+        let mut hero = hero();
+        hero.elemental.element = ELEMENT_WATER;
+        let armor = Item::generate(Pubkey::new_unique(), ITEM_TYPE_ARMOR, RARITY_MYTHIC, ELEMENT_FIRE, 0);
+        let accessory = Item::generate(Pubkey::new_unique(), ITEM_TYPE_ACCESSORY, RARITY_MYTHIC, ELEMENT_FIRE, 0);
+        let weapon = Item::generate(Pubkey::new_unique(), ITEM_TYPE_WEAPON, RARITY_COMMON, ELEMENT_DARK, 0);
+
+        let elemental = hero.calculate_elemental(&[&weapon, &armor]);
+        assert_eq!(elemental.element, ELEMENT_WATER);
+        assert_eq!(elemental.resistance(ELEMENT_FIRE), 3000);
+        assert_eq!(elemental.resistance(ELEMENT_DARK), 500);
+        assert_eq!(elemental.resistance(ELEMENT_WATER), 0);
+
+        // Stacked resistances stop at the cap
+        let mut legendary = accessory.clone();
+        legendary.rarity = RARITY_LEGENDARY;
+        let stacked = hero.calculate_elemental(&[&armor, &accessory, &legendary]);
+        assert_eq!(stacked.resistance(ELEMENT_FIRE), 8500);
+        hero.elemental.resistances[ELEMENT_FIRE as usize] = 1000;
+        let capped = hero.calculate_elemental(&[&armor, &accessory, &legendary]);
+        assert_eq!(capped.resistance(ELEMENT_FIRE), MAX_ELEMENT_RESISTANCE);
+    }
+
+    #[test]
+    fn test_migrate_legacy_layout() {
+        let owner = Pubkey::new_unique();
+        let player = Pubkey::new_unique();
+        let mut legacy = Hero::DISCRIMINATOR.to_vec();
+        legacy.extend_from_slice(owner.as_ref());
+        legacy.extend_from_slice(player.as_ref());
+        legacy.push(1);
+        legacy.extend_from_slice(&3u16.to_le_bytes());
+        legacy.extend_from_slice(&40u32.to_le_bytes());
+        for value in [12u16, 11, 10, 9, 8, 7, 6, 5] {
+            legacy.extend_from_slice(&value.to_le_bytes());
+        }
+        legacy.extend_from_slice(&[0, 0, 0]); // No equipment
+        legacy.extend_from_slice(&6u32.to_le_bytes());
+        legacy.extend_from_slice(&5u32.to_le_bytes());
+        legacy.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        legacy.push(254);
+        legacy.resize(LegacyHero::LEN, 0); // Room the options had when the account was created
+
+        let hero = Hero::from_legacy(&legacy).unwrap();
+        assert_eq!(hero.owner, owner);
+        assert_eq!(hero.player, player);
+        assert_eq!(hero.index, 1);
+        assert_eq!(hero.level, 3);
+        assert_eq!(hero.xp, 40);
+        assert_eq!(hero.base_attributes.strength, 12);
+        assert_eq!(hero.base_attributes.luck, 5);
+        assert_eq!(hero.equipped_weapon, None);
+        assert_eq!(hero.total_battles, 6);
+        assert_eq!(hero.total_victories, 5);
+        assert_eq!(hero.created_at, 1_700_000_000);
+        assert_eq!(hero.bump, 254);
+        assert_eq!(hero.hero_class, HERO_CLASS_WARRIOR);
+        assert_eq!(hero.elemental.element, CLASS_SKILL_ELEMENTS[HERO_CLASS_WARRIOR as usize]);
+        assert_eq!(hero.item_count, 0);
+
+        // The rebuilt account fits the new size and reads back as a Hero
+        let mut data = Vec::new();
+        hero.try_serialize(&mut data).unwrap();
+        assert!(data.len() <= Hero::LEN);
+        let migrated = Hero::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.total_victories, 5);
+        assert_eq!(migrated.hero_class, HERO_CLASS_WARRIOR);
+
+        // Already migrated or foreign accounts are rejected
+        data.resize(Hero::LEN, 0);
+        assert!(matches!(
+            Hero::from_legacy(&data),
+            Err(error) if error == crate::errors::RpgError::InvalidLegacyLayout.into()
+        ));
+        legacy[0] ^= 1;
+        assert!(matches!(
+            Hero::from_legacy(&legacy),
+            Err(error) if error == crate::errors::RpgError::InvalidLegacyLayout.into()
+        ));
+    }
+}
@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
 use crate::state::Attributes;
 
 #[account]
@@ -10,6 +12,10 @@ pub struct Item {
     pub bonus_attributes: Attributes,
     pub created_at: i64,
     pub bump: u8,
+    // Fields below were appended after launch. No instruction created items before them, so
+    // there are no legacy items to migrate
+    pub element: u8,
+    pub durability: u16, // Worn down by battles while equipped, restored by repairs
 }
 
 impl Item {
@@ -20,7 +26,9 @@ impl Item {
         32 + // name
         (2 * 8) + // bonus_attributes (8 u16s)
         8 +  // created_at
-        1;   // bump
+        1 +  // bump
+        1 +  // element
+        2;   // durability
 
     pub fn get_name_string(&self) -> String {
         String::from_utf8_lossy(&self.name)
@@ -39,4 +47,161 @@ impl Item {
     pub fn is_accessory(&self) -> bool {
         self.item_type == crate::constants::ITEM_TYPE_ACCESSORY
     }
-}
\ No newline at end of file
+
+    /// Generated item for a hero, the bump is filled in when its account is created
+    pub fn generate(hero: Pubkey, item_type: u8, rarity: u8, element: u8, created_at: i64) -> Self {
+        Self {
+            hero,
+            item_type,
+            rarity,
+            name: Self::generated_name(item_type, rarity),
+            bonus_attributes: Self::bonus_for(item_type, rarity),
+            created_at,
+            bump: 0,
+            element,
+            durability: MAX_ITEM_DURABILITY,
+        }
+    }
+
+    /// Bonus this item adds in battle, shrinking with durability down to nothing once broken
+    pub fn effective_bonus(&self) -> Attributes {
+        self.bonus_attributes
+            .scaled(self.durability.min(MAX_ITEM_DURABILITY), MAX_ITEM_DURABILITY)
+    }
+
+    /// Resistance this item gives against its own element, neutral items give none
+    pub fn effective_resistance(&self) -> i16 {
+        if self.element == ELEMENT_NEUTRAL {
+            return 0;
+        }
+        let resistance = ITEM_RESISTANCE_BY_RARITY[std::cmp::min(self.rarity, RARITY_MYTHIC) as usize];
+        let durability = self.durability.min(MAX_ITEM_DURABILITY);
+        (resistance as u32 * durability as u32).div_ceil(MAX_ITEM_DURABILITY as u32) as i16
+    }
+
+    pub fn wear(&mut self, amount: u16) {
+        self.durability = self.durability.saturating_sub(amount);
+    }
+
+    pub fn repair(&mut self) -> Result<()> {
+        require!(self.durability < MAX_ITEM_DURABILITY, RpgError::ItemNotDamaged);
+        self.durability = MAX_ITEM_DURABILITY;
+        Ok(())
+    }
+
+    /// Raise the item to the next rarity with that rarity's bonus and name
+    pub fn upgrade(&mut self) -> Result<u8> {
+        require!(self.rarity < RARITY_MYTHIC, RpgError::InvalidItemRarity);
+        self.rarity += 1;
+        self.bonus_attributes = Self::bonus_for(self.item_type, self.rarity);
+        self.name = Self::generated_name(self.item_type, self.rarity);
+        Ok(self.rarity)
+    }
+
+    /// Attribute bonus for a generated item of the given type and rarity
+    pub fn bonus_for(item_type: u8, rarity: u8) -> Attributes {
+        let amount = ITEM_BONUS_BY_RARITY[std::cmp::min(rarity, RARITY_MYTHIC) as usize];
+        let mut bonus = Attributes::default();
+
+        match item_type {
+            ITEM_TYPE_WEAPON => {
+                bonus.strength = amount;
+                bonus.precision = amount / 2;
+            }
+            ITEM_TYPE_ARMOR => {
+                bonus.vitality = amount;
+                bonus.wisdom = amount / 2;
+            }
+            _ => {
+                bonus.agility = amount;
+                bonus.luck = amount / 2;
+            }
+        }
+
+        bonus
+    }
+
+    /// Generated display name such as "Rare Weapon"
+    pub fn generated_name(item_type: u8, rarity: u8) -> [u8; 32] {
+        let rarity_name = match rarity {
+            RARITY_COMMON => "Common",
+            RARITY_UNCOMMON => "Uncommon",
+            RARITY_RARE => "Rare",
+            RARITY_EPIC => "Epic",
+            RARITY_LEGENDARY => "Legendary",
+            _ => "Mythic",
+        };
+        let type_name = match item_type {
+            ITEM_TYPE_WEAPON => "Weapon",
+            ITEM_TYPE_ARMOR => "Armor",
+            _ => "Accessory",
+        };
+
+        let full_name = format!("{} {}", rarity_name, type_name);
+        let mut name = [0u8; 32];
+        name[..full_name.len()].copy_from_slice(full_name.as_bytes());
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wear_and_repair() {
+        let mut item = Item::generate(Pubkey::new_unique(), ITEM_TYPE_WEAPON, RARITY_COMMON, ELEMENT_NEUTRAL, 0);
+        assert_eq!(item.durability, MAX_ITEM_DURABILITY);
+        assert!(item.repair().is_err());
+
+        item.wear(ITEM_WEAR_PER_BATTLE);
+        assert_eq!(item.durability, MAX_ITEM_DURABILITY - ITEM_WEAR_PER_BATTLE);
+        item.wear(u16::MAX);
+        assert_eq!(item.durability, 0);
+
+        item.repair().unwrap();
+        assert_eq!(item.durability, MAX_ITEM_DURABILITY);
+    }
+
+    #[test]
+    fn test_effective_bonus() {
+        let mut item = Item::generate(Pubkey::new_unique(), ITEM_TYPE_WEAPON, RARITY_EPIC, ELEMENT_NEUTRAL, 0);
+        assert_eq!(item.effective_bonus().strength, 7);
+        assert_eq!(item.effective_bonus().precision, 3);
+
+        // Worn items round up, broken ones give nothing
+        item.wear(MAX_ITEM_DURABILITY / 2);
+        assert_eq!(item.effective_bonus().strength, 4);
+        assert_eq!(item.effective_bonus().precision, 2);
+        item.wear(u16::MAX);
+        assert_eq!(item.effective_bonus().strength, 0);
+        assert_eq!(item.effective_bonus().total(), 0);
+    }
+
+    #[test]
+    fn test_effective_resistance() {
+        let neutral = Item::generate(Pubkey::new_unique(), ITEM_TYPE_ARMOR, RARITY_MYTHIC, ELEMENT_NEUTRAL, 0);
+        assert_eq!(neutral.effective_resistance(), 0);
+
+        let mut item = Item::generate(Pubkey::new_unique(), ITEM_TYPE_ARMOR, RARITY_RARE, ELEMENT_FIRE, 0);
+        assert_eq!(item.effective_resistance(), 1500);
+        item.wear(MAX_ITEM_DURABILITY / 2);
+        assert_eq!(item.effective_resistance(), 750);
+        item.wear(u16::MAX);
+        assert_eq!(item.effective_resistance(), 0);
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let mut item = Item::generate(Pubkey::new_unique(), ITEM_TYPE_ARMOR, RARITY_EPIC, ELEMENT_NEUTRAL, 0);
+
+        assert_eq!(item.upgrade().unwrap(), RARITY_LEGENDARY);
+        assert_eq!(item.bonus_attributes.vitality, ITEM_BONUS_BY_RARITY[RARITY_LEGENDARY as usize]);
+        assert_eq!(item.get_name_string(), "Legendary Armor");
+
+        item.upgrade().unwrap();
+        assert_eq!(item.rarity, RARITY_MYTHIC);
+        assert!(item.upgrade().is_err());
+        assert_eq!(item.rarity, RARITY_MYTHIC);
+    }
+}
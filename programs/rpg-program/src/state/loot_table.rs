@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct LootEntry {
+    pub item_type: u8,
+    pub min_rarity: u8,
+    pub max_rarity: u8,
+    pub element: u8,
+    pub weight: u16,      // Relative weight when picking an entry
+    pub drop_chance: u16, // Out of 10000, before luck bonus
+}
+
+impl LootEntry {
+    pub const LEN: usize = 1 + // item_type
+        1 + // min_rarity
+        1 + // max_rarity
+        1 + // element
+        2 + // weight
+        2;  // drop_chance
+}
+
+#[account]
+pub struct LootTable {
+    pub enemy_template: Pubkey,
+    pub entry_count: u8,
+    pub entries: [LootEntry; MAX_LOOT_ENTRIES],
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl LootTable {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // enemy_template
+        1 +  // entry_count
+        (LootEntry::LEN * MAX_LOOT_ENTRIES) + // entries
+        8 +  // created_at
+        1;   // bump
+
+    pub fn active_entries(&self) -> &[LootEntry] {
+        let count = std::cmp::min(self.entry_count as usize, MAX_LOOT_ENTRIES);
+        &self.entries[..count]
+    }
+
+    pub fn set_entries(&mut self, entries: &[LootEntry]) {
+        self.entries = [LootEntry::default(); MAX_LOOT_ENTRIES];
+        self.entries[..entries.len()].copy_from_slice(entries);
+        self.entry_count = entries.len() as u8;
+    }
+}
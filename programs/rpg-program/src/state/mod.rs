@@ -5,6 +5,10 @@ pub mod roll_session;
 pub mod enemy_template;
 pub mod treasury;
 pub mod item;
+pub mod loot_table;
+pub mod game_config;
+pub mod odds_table;
+pub mod banner;
 
 pub use player::*;
 pub use hero::*;
@@ -13,8 +17,13 @@ pub use roll_session::*;
 pub use enemy_template::*;
 pub use treasury::*;
 pub use item::*;
+pub use loot_table::*;
+pub use game_config::*;
+pub use odds_table::*;
+pub use banner::*;
 
 use anchor_lang::prelude::*;
+use crate::constants::*;
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
 pub struct Attributes {
@@ -28,6 +37,25 @@ pub struct Attributes {
     pub luck: u16,         // Loot quality, rare drops
 }
 
+/// Element used for attacks and skills plus resistances (out of 10000, negative = weakness)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ElementalProfile {
+    pub element: u8,
+    pub resistances: [i16; ELEMENT_COUNT],
+}
+
+impl ElementalProfile {
+    pub const LEN: usize = 1 + // element
+        (2 * ELEMENT_COUNT); // resistances
+
+    pub fn resistance(&self, element: u8) -> i16 {
+        self.resistances
+            .get(element as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
 impl Attributes {
     pub fn new_base() -> Self {
         Self {
@@ -42,6 +70,27 @@ impl Attributes {
         }
     }
 
+    pub fn from_array(values: [u16; 8]) -> Self {
+        Self {
+            strength: values[0],
+            dexterity: values[1],
+            vitality: values[2],
+            intelligence: values[3],
+            wisdom: values[4],
+            agility: values[5],
+            precision: values[6],
+            luck: values[7],
+        }
+    }
+
+    pub fn new_for_class(hero_class: u8) -> Self {
+        Self::from_array(CLASS_STARTING_ATTRIBUTES[hero_class as usize % HERO_CLASS_COUNT])
+    }
+
+    pub fn class_level_bonus(hero_class: u8) -> Self {
+        Self::from_array(CLASS_LEVEL_BONUS[hero_class as usize % HERO_CLASS_COUNT])
+    }
+
     pub fn add(&mut self, other: &Attributes) {
         self.strength = self.strength.saturating_add(other.strength);
         self.dexterity = self.dexterity.saturating_add(other.dexterity);
@@ -53,6 +102,23 @@ impl Attributes {
         self.luck = self.luck.saturating_add(other.luck);
     }
 
+    /// Each attribute scaled by `numerator / denominator`, rounded up so only a zero numerator clears it
+    pub fn scaled(&self, numerator: u16, denominator: u16) -> Self {
+        let scale = |value: u16| {
+            (value as u32 * numerator as u32).div_ceil(denominator.max(1) as u32) as u16
+        };
+        Self {
+            strength: scale(self.strength),
+            dexterity: scale(self.dexterity),
+            vitality: scale(self.vitality),
+            intelligence: scale(self.intelligence),
+            wisdom: scale(self.wisdom),
+            agility: scale(self.agility),
+            precision: scale(self.precision),
+            luck: scale(self.luck),
+        }
+    }
+
     pub fn total(&self) -> u32 {
         self.strength as u32
             + self.dexterity as u32
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+
+/// Published roll odds, weights are out of ODDS_TOTAL_WEIGHT and readable by anyone
+#[account]
+pub struct OddsTable {
+    pub id: u32,
+    pub weights: [u16; RARITY_COUNT],    // Chance of each rarity, out of ODDS_TOTAL_WEIGHT
+    pub xp_rewards: [u32; RARITY_COUNT], // XP awarded for each rarity
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl OddsTable {
+    pub const LEN: usize = 8 + // discriminator
+        4 +  // id
+        (2 * RARITY_COUNT) + // weights
+        (4 * RARITY_COUNT) + // xp_rewards
+        8 +  // updated_at
+        1;   // bump
+
+    pub fn xp_reward(&self, rarity: u8) -> u32 {
+        self.xp_rewards[rarity as usize]
+    }
+}
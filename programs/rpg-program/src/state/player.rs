@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use crate::constants::*;
+use crate::state::EconomyConfig;
 
 #[account]
 pub struct Player {
@@ -11,9 +11,31 @@ pub struct Player {
     pub total_sol_spent: u64,
     pub created_at: i64,
     pub bump: u8,
+    // Fields below were appended after launch, see `Player::from_legacy`
+    pub gold: u64,              // Spendable soft currency
+    pub total_gold_earned: u64,
+    pub total_gold_spent: u64,
+    pub referrer: Option<Pubkey>,    // Referrer's Player account, set once at initialization
+    pub referral_count: u32,         // Players referred
+    pub referee_sol_spent: u64,      // SOL spent by referees that earned a reward
+    pub referral_balance: u64,       // Claimable lamports
+    pub total_referral_earned: u64,
 }
 
-impl Player {
+/// Player layout before gold and referrals were added
+#[derive(AnchorDeserialize)]
+pub struct LegacyPlayer {
+    pub authority: Pubkey,
+    pub hero_count: u8,
+    pub total_battles: u64,
+    pub total_victories: u64,
+    pub total_xp_earned: u64,
+    pub total_sol_spent: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl LegacyPlayer {
     pub const LEN: usize = 8 + // discriminator
         32 + // authority
         1 +  // hero_count
@@ -23,9 +45,58 @@ impl Player {
         8 +  // total_sol_spent
         8 +  // created_at
         1;   // bump
+}
 
-    pub fn can_add_hero(&self) -> bool {
-        self.hero_count < MAX_HEROES_PER_PLAYER
+impl Player {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // authority
+        1 +  // hero_count
+        8 +  // total_battles
+        8 +  // total_victories
+        8 +  // total_xp_earned
+        8 +  // total_sol_spent
+        8 +  // created_at
+        1 +  // bump
+        8 +  // gold
+        8 +  // total_gold_earned
+        8 +  // total_gold_spent
+        (1 + 32) + // referrer (Option<Pubkey>)
+        4 +  // referral_count
+        8 +  // referee_sol_spent
+        8 +  // referral_balance
+        8;   // total_referral_earned
+
+    /// Rebuild a legacy player in the current layout with no gold and no referrer
+    pub fn from_legacy(data: &[u8]) -> Result<Player> {
+        require!(
+            data.len() == LegacyPlayer::LEN && data.starts_with(Player::DISCRIMINATOR),
+            crate::errors::RpgError::InvalidLegacyLayout
+        );
+        let legacy = LegacyPlayer::deserialize(&mut &data[8..])
+            .map_err(|_| crate::errors::RpgError::InvalidLegacyLayout)?;
+
+        Ok(Player {
+            authority: legacy.authority,
+            hero_count: legacy.hero_count,
+            total_battles: legacy.total_battles,
+            total_victories: legacy.total_victories,
+            total_xp_earned: legacy.total_xp_earned,
+            total_sol_spent: legacy.total_sol_spent,
+            created_at: legacy.created_at,
+            bump: legacy.bump,
+            gold: 0,
+            total_gold_earned: 0,
+            total_gold_spent: 0,
+            referrer: None,
+            referral_count: 0,
+            referee_sol_spent: 0,
+            referral_balance: 0,
+            total_referral_earned: 0,
+        })
+    }
+
+    pub fn can_add_hero(&self, config: &EconomyConfig) -> bool {
+        self.hero_count < config.max_heroes_per_player
     }
 
     pub fn add_hero(&mut self) {
@@ -43,4 +114,102 @@ impl Player {
     pub fn add_sol_spent(&mut self, amount: u64) {
         self.total_sol_spent = self.total_sol_spent.saturating_add(amount);
     }
-}
\ No newline at end of file
+
+    pub fn refund_sol_spent(&mut self, amount: u64) {
+        self.total_sol_spent = self.total_sol_spent.saturating_sub(amount);
+    }
+
+    pub fn add_gold(&mut self, amount: u64) {
+        self.gold = self.gold.saturating_add(amount);
+        self.total_gold_earned = self.total_gold_earned.saturating_add(amount);
+    }
+
+    pub fn add_referral_reward(&mut self, referee_spent: u64, reward: u64) {
+        self.referee_sol_spent = self.referee_sol_spent.saturating_add(referee_spent);
+        self.referral_balance = self.referral_balance.saturating_add(reward);
+        self.total_referral_earned = self.total_referral_earned.saturating_add(reward);
+    }
+
+    /// Empty the claimable referral balance and return it
+    pub fn claim_referral_balance(&mut self) -> Result<u64> {
+        let amount = self.referral_balance;
+        require!(amount > 0, crate::errors::RpgError::NothingToClaim);
+        self.referral_balance = 0;
+        Ok(amount)
+    }
+
+    /// Deduct gold for a purchase, failing without changes if the balance is too low
+    pub fn spend_gold(&mut self, amount: u64) -> Result<()> {
+        self.gold = self
+            .gold
+            .checked_sub(amount)
+            .ok_or(crate::errors::RpgError::InsufficientGold)?;
+        self.total_gold_spent = self.total_gold_spent.saturating_add(amount);
+        Ok(())
+    }
+}
+
+/// Referrer to credit with a resolved entry's reward, which must be passed when one is owed
+pub fn referral_for<'a>(
+    referrer: &'a mut Option<Account<'_, Player>>,
+    reward: u64,
+) -> Result<Option<(&'a mut Player, u64)>> {
+    if reward == 0 {
+        return Ok(None);
+    }
+    let referrer = referrer
+        .as_deref_mut()
+        .ok_or(crate::errors::RpgError::InvalidReferrer)?;
+    Ok(Some((referrer, reward)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::RpgError;
+
+    #[test]
+    fn test_migrate_legacy_layout() {
+        let authority = Pubkey::new_unique();
+        let mut legacy = Player::DISCRIMINATOR.to_vec();
+        legacy.extend_from_slice(authority.as_ref());
+        legacy.push(2);
+        for value in [7u64, 4, 900, 30_000_000] {
+            legacy.extend_from_slice(&value.to_le_bytes());
+        }
+        legacy.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        legacy.push(254);
+        assert_eq!(legacy.len(), LegacyPlayer::LEN);
+
+        let player = Player::from_legacy(&legacy).unwrap();
+        assert_eq!(player.authority, authority);
+        assert_eq!(player.hero_count, 2);
+        assert_eq!(player.total_battles, 7);
+        assert_eq!(player.total_victories, 4);
+        assert_eq!(player.total_xp_earned, 900);
+        assert_eq!(player.total_sol_spent, 30_000_000);
+        assert_eq!(player.created_at, 1_700_000_000);
+        assert_eq!(player.bump, 254);
+        assert_eq!(player.gold, 0);
+        assert_eq!(player.referrer, None);
+
+        // The legacy fields keep their offsets and the rebuilt account fits the new size
+        let mut data = Vec::new();
+        player.try_serialize(&mut data).unwrap();
+        assert!(data.len() <= Player::LEN);
+        assert_eq!(data[..LegacyPlayer::LEN], legacy[..]);
+        let migrated = Player::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.total_sol_spent, 30_000_000);
+
+        // Already migrated or foreign accounts are rejected
+        assert!(matches!(
+            Player::from_legacy(&data),
+            Err(error) if error == RpgError::InvalidLegacyLayout.into()
+        ));
+        legacy[0] ^= 1;
+        assert!(matches!(
+            Player::from_legacy(&legacy),
+            Err(error) if error == RpgError::InvalidLegacyLayout.into()
+        ));
+    }
+}
@@ -1,7 +1,40 @@
 use anchor_lang::prelude::*;
+use crate::constants::{RARITY_COUNT, ROLL_BATCH_SIZE};
+use crate::state::OracleLayout;
+use crate::utils::randomness::SessionOracle;
 
 #[account]
 pub struct RollSession {
+    pub hero: Pubkey,
+    pub player: Pubkey,
+    pub nonce: u64,
+    pub commit_slot: u64,
+    pub state: u8,
+    pub reward_rarity: Option<u8>,        // Best rarity rolled
+    pub reward_xp: Option<u32>,           // Total XP awarded
+    pub created_at: i64,
+    pub bump: u8,
+    // Fields below were appended after launch, legacy sessions are closed by `close_legacy_roll_session`
+    pub recommit_count: u8,
+    pub randomness_provider: u8,
+    pub oracle_program: Pubkey,
+    pub secret_hash: [u8; 32], // Hash of the player's secret, see hash_player_secret
+    pub odds_table: Pubkey,
+    pub banner: Option<Pubkey>, // Banner rolled on, None for standard rolls
+    pub cost: u64,              // Lamports or tokens paid into the treasury, refunded on cancel
+    pub payment_mint: Option<Pubkey>, // Mint paid in, None for SOL
+    pub referral_reward: u64,         // Credited to the player's referrer once resolved
+    pub roll_count: u8,                   // 1, or ROLL_BATCH_SIZE for a batch
+    pub rarities: [u8; ROLL_BATCH_SIZE],  // Result of every roll in order
+    pub odds_weights: [u16; RARITY_COUNT],    // Odds table weights when the session was paid for
+    pub odds_xp_rewards: [u32; RARITY_COUNT], // Odds table XP rewards when the session was paid for
+    pub oracle_layout: OracleLayout,          // Request layout of oracle_program when the session started
+}
+
+/// Roll session layout before escrow, oracles and batches were added. Sessions can't be carried
+/// over without those, so legacy ones are only closed
+#[derive(AnchorDeserialize)]
+pub struct LegacyRollSession {
     pub hero: Pubkey,
     pub player: Pubkey,
     pub nonce: u64,
@@ -13,7 +46,7 @@ pub struct RollSession {
     pub bump: u8,
 }
 
-impl RollSession {
+impl LegacyRollSession {
     pub const LEN: usize = 8 + // discriminator
         32 + // hero
         32 + // player
@@ -25,6 +58,55 @@ impl RollSession {
         8 +  // created_at
         1;   // bump
 
+    /// Read a legacy roll session account, rejecting accounts in any other layout
+    pub fn from_account_data(data: &[u8]) -> Result<LegacyRollSession> {
+        require!(
+            data.len() == LegacyRollSession::LEN && data.starts_with(RollSession::DISCRIMINATOR),
+            crate::errors::RpgError::InvalidLegacyLayout
+        );
+        LegacyRollSession::deserialize(&mut &data[8..])
+            .map_err(|_| crate::errors::RpgError::InvalidLegacyLayout.into())
+    }
+}
+
+impl RollSession {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // hero
+        32 + // player
+        8 +  // nonce
+        8 +  // commit_slot
+        1 +  // state
+        (1 + 1) + // reward_rarity (Option<u8>)
+        (1 + 4) + // reward_xp (Option<u32>)
+        8 +  // created_at
+        1 +  // bump
+        1 +  // recommit_count
+        1 +  // randomness_provider
+        32 + // oracle_program
+        32 + // secret_hash
+        32 + // odds_table
+        (1 + 32) + // banner (Option<Pubkey>)
+        8 +  // cost
+        (1 + 32) + // payment_mint (Option<Pubkey>)
+        8 +  // referral_reward
+        1 +  // roll_count
+        ROLL_BATCH_SIZE + // rarities
+        (2 * RARITY_COUNT) + // odds_weights
+        (4 * RARITY_COUNT) + // odds_xp_rewards
+        OracleLayout::LEN;   // oracle_layout
+
+    /// Oracle the session was started with, reading `request` if one was passed
+    pub fn oracle<'a, 'info>(
+        &'a self,
+        request: Option<&'a AccountInfo<'info>>,
+    ) -> SessionOracle<'a, 'info> {
+        SessionOracle {
+            program: &self.oracle_program,
+            layout: &self.oracle_layout,
+            request,
+        }
+    }
+
     pub fn is_pending(&self) -> bool {
         self.state == crate::constants::ROLL_STATE_PENDING
     }
@@ -33,9 +115,49 @@ impl RollSession {
         self.state == crate::constants::ROLL_STATE_COMPLETED
     }
 
-    pub fn complete_roll(&mut self, rarity: u8, xp: u32) {
+    pub fn odds_xp_reward(&self, rarity: u8) -> u32 {
+        self.odds_xp_rewards[rarity as usize]
+    }
+
+    pub fn complete_rolls(&mut self, rarities: &[u8], total_xp: u32) {
         self.state = crate::constants::ROLL_STATE_COMPLETED;
-        self.reward_rarity = Some(rarity);
-        self.reward_xp = Some(xp);
+        self.rarities[..rarities.len()].copy_from_slice(rarities);
+        self.reward_rarity = rarities.iter().max().copied();
+        self.reward_xp = Some(total_xp);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::ROLL_STATE_PENDING;
+
+    #[test]
+    fn test_read_legacy_layout() {
+        let hero = Pubkey::new_unique();
+        let player = Pubkey::new_unique();
+        let mut legacy = RollSession::DISCRIMINATOR.to_vec();
+        legacy.extend_from_slice(hero.as_ref());
+        legacy.extend_from_slice(player.as_ref());
+        legacy.extend_from_slice(&3u64.to_le_bytes());
+        legacy.extend_from_slice(&500u64.to_le_bytes());
+        legacy.extend_from_slice(&[ROLL_STATE_PENDING, 0, 0]); // No rewards yet
+        legacy.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        legacy.push(254);
+        legacy.resize(LegacyRollSession::LEN, 0); // Room the options had when the account was created
+
+        let session = LegacyRollSession::from_account_data(&legacy).unwrap();
+        assert_eq!(session.hero, hero);
+        assert_eq!(session.player, player);
+        assert_eq!(session.nonce, 3);
+        assert_eq!(session.commit_slot, 500);
+        assert_eq!(session.state, ROLL_STATE_PENDING);
+        assert_eq!(session.reward_rarity, None);
+        assert_eq!(session.created_at, 1_700_000_000);
+        assert_eq!(session.bump, 254);
+
+        // Foreign accounts are rejected
+        legacy[0] ^= 1;
+        assert!(LegacyRollSession::from_account_data(&legacy).is_err());
     }
-}
\ No newline at end of file
+}
@@ -1,7 +1,49 @@
 use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::Player;
+use crate::utils::math::split_revenue;
+
+/// Revenue collected in one SPL token mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MintRevenue {
+    pub mint: Pubkey, // Default key marks an unused slot
+    pub total_collected: u64,
+    pub total_withdrawn: u64,
+    pub escrow: u64, // Paid for unresolved rolls and battles, refunded on cancel
+}
+
+impl MintRevenue {
+    pub const LEN: usize = 32 + // mint
+        8 +  // total_collected
+        8 +  // total_withdrawn
+        8;   // escrow
+}
 
 #[account]
 pub struct Treasury {
+    pub authority: Pubkey,
+    pub total_collected: u64,
+    pub total_battles: u64,
+    pub total_rolls: u64,
+    pub total_heroes_created: u64,
+    pub total_withdrawn: u64,
+    pub reserve: u64, // Lamports kept for refunds, pending escrow counts towards it and the rest is held in the dev bucket
+    pub vault_bump: u8, // Fees are held by the vault PDA, not this account
+    pub token_revenue: [MintRevenue; MAX_PAYMENT_MINTS], // Mints with a treasury token account
+    pub revenue_shares: [u16; REVENUE_BUCKET_COUNT],     // Basis points of SOL fees per bucket
+    pub bucket_recipients: [Pubkey; REVENUE_BUCKET_COUNT], // Wallet allowed to claim each bucket
+    pub bucket_balances: [u64; REVENUE_BUCKET_COUNT],    // Unclaimed lamports per bucket
+    pub bucket_claimed: [u64; REVENUE_BUCKET_COUNT],
+    pub referral_owed: u64, // Unclaimed referral rewards, taken off the top of referred fees
+    pub pending_escrow: u64, // SOL paid for unresolved rolls and battles, refunded on cancel
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// Treasury layout before the vault, revenue buckets and escrow were added
+#[derive(AnchorDeserialize)]
+pub struct LegacyTreasury {
     pub authority: Pubkey,
     pub total_collected: u64,
     pub total_battles: u64,
@@ -11,6 +53,17 @@ pub struct Treasury {
     pub bump: u8,
 }
 
+impl LegacyTreasury {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // authority
+        8 +  // total_collected
+        8 +  // total_battles
+        8 +  // total_rolls
+        8 +  // total_heroes_created
+        8 +  // created_at
+        1;   // bump
+}
+
 impl Treasury {
     pub const LEN: usize = 8 + // discriminator
         32 + // authority
@@ -18,22 +71,216 @@ impl Treasury {
         8 +  // total_battles
         8 +  // total_rolls
         8 +  // total_heroes_created
+        8 +  // total_withdrawn
+        8 +  // reserve
+        1 +  // vault_bump
+        (MintRevenue::LEN * MAX_PAYMENT_MINTS) + // token_revenue
+        (2 * REVENUE_BUCKET_COUNT) +  // revenue_shares
+        (32 * REVENUE_BUCKET_COUNT) + // bucket_recipients
+        (8 * REVENUE_BUCKET_COUNT) +  // bucket_balances
+        (8 * REVENUE_BUCKET_COUNT) +  // bucket_claimed
+        8 +  // referral_owed
+        8 +  // pending_escrow
         8 +  // created_at
         1;   // bump
 
-    pub fn add_revenue(&mut self, amount: u64, revenue_type: RevenueType) {
+    /// Rebuild a legacy treasury in the current layout, fees it held move to the dev bucket
+    pub fn from_legacy(data: &[u8], vault_bump: u8, migrated: u64) -> Result<Treasury> {
+        require!(
+            data.len() == LegacyTreasury::LEN && data.starts_with(Treasury::DISCRIMINATOR),
+            RpgError::InvalidTreasuryLayout
+        );
+        let legacy = LegacyTreasury::deserialize(&mut &data[8..])
+            .map_err(|_| RpgError::InvalidTreasuryLayout)?;
+
+        let mut bucket_balances = [0; REVENUE_BUCKET_COUNT];
+        bucket_balances[REVENUE_BUCKET_DEV] = migrated;
+
+        Ok(Treasury {
+            authority: legacy.authority,
+            total_collected: legacy.total_collected,
+            total_battles: legacy.total_battles,
+            total_rolls: legacy.total_rolls,
+            total_heroes_created: legacy.total_heroes_created,
+            total_withdrawn: 0,
+            reserve: TREASURY_RESERVE,
+            vault_bump,
+            token_revenue: [MintRevenue::default(); MAX_PAYMENT_MINTS],
+            revenue_shares: [REVENUE_SHARE_TOTAL, 0, 0, 0],
+            bucket_recipients: [legacy.authority; REVENUE_BUCKET_COUNT],
+            bucket_balances,
+            bucket_claimed: [0; REVENUE_BUCKET_COUNT],
+            referral_owed: 0,
+            pending_escrow: 0,
+            created_at: legacy.created_at,
+            bump: legacy.bump,
+        })
+    }
+
+    /// Record a SOL fee, a referrer's reward comes off the top and the rest is divided between the buckets
+    pub fn add_revenue(&mut self, amount: u64, revenue_type: RevenueType, referral: Option<(&mut Player, u64)>) {
         self.total_collected = self.total_collected.saturating_add(amount);
-        
+        let sales = self.sales_mut(revenue_type);
+        *sales = sales.saturating_add(1);
+
+        let mut remaining = amount;
+        if let Some((referrer, reward)) = referral {
+            let reward = std::cmp::min(reward, amount);
+            remaining -= reward;
+            self.accrue_referral(referrer, amount, reward);
+        }
+
+        let split = split_revenue(remaining, &self.revenue_shares);
+        for (balance, share) in self.bucket_balances.iter_mut().zip(split) {
+            *balance = balance.saturating_add(share);
+        }
+    }
+
+    pub fn record_withdrawal(&mut self, amount: u64) {
+        self.total_withdrawn = self.total_withdrawn.saturating_add(amount);
+    }
+
+    /// Hold a roll or battle entry fee until the session resolves or is cancelled
+    pub fn escrow_entry(&mut self, amount: u64) {
+        self.pending_escrow = self.pending_escrow.saturating_add(amount);
+    }
+
+    /// A resolved entry can no longer be refunded, its fee is split like any other
+    pub fn release_entry(&mut self, amount: u64, revenue_type: RevenueType, referral: Option<(&mut Player, u64)>) {
+        self.pending_escrow = self.pending_escrow.saturating_sub(amount);
+        self.add_revenue(amount, revenue_type, referral);
+    }
+
+    /// Take a cancelled entry out of escrow, the buckets never saw it
+    pub fn refund_entry(&mut self, amount: u64) {
+        self.pending_escrow = self.pending_escrow.saturating_sub(amount);
+    }
+
+    /// Part of the reserve not already held as pending escrow
+    pub fn reserve_excess(&self) -> u64 {
+        self.reserve.saturating_sub(self.pending_escrow)
+    }
+
+    /// Lamports owed to bucket recipients, referrers and pending entries, withdrawals can't touch them
+    pub fn allocated_balance(&self) -> u64 {
+        self.bucket_balances
+            .iter()
+            .fold(
+                self.referral_owed.saturating_add(self.pending_escrow),
+                |total, balance| total.saturating_add(*balance),
+            )
+    }
+
+    /// Owe a referrer `reward` for a referee's SOL spend, spend that earns nothing isn't recorded
+    fn accrue_referral(&mut self, referrer: &mut Player, spent: u64, reward: u64) {
+        if reward == 0 {
+            return;
+        }
+        self.referral_owed = self.referral_owed.saturating_add(reward);
+        referrer.add_referral_reward(spent, reward);
+    }
+
+    pub fn pay_referral(&mut self, amount: u64) {
+        self.referral_owed = self.referral_owed.saturating_sub(amount);
+    }
+
+    /// Empty a bucket for its recipient and return the claimed amount
+    pub fn claim_bucket(&mut self, bucket: usize) -> Result<u64> {
+        require!(bucket < REVENUE_BUCKET_COUNT, RpgError::InvalidRevenueSplit);
+        let amount = if bucket == REVENUE_BUCKET_DEV {
+            self.withdrawable_dev_balance()
+        } else {
+            self.bucket_balances[bucket]
+        };
+        require!(amount > 0, RpgError::NothingToClaim);
+
+        self.bucket_balances[bucket] -= amount;
+        self.bucket_claimed[bucket] = self.bucket_claimed[bucket].saturating_add(amount);
+        Ok(amount)
+    }
+
+    /// Dev bucket lamports that can be paid out, the reserve beyond pending escrow stays behind
+    pub fn withdrawable_dev_balance(&self) -> u64 {
+        self.bucket_balances[REVENUE_BUCKET_DEV].saturating_sub(self.reserve_excess())
+    }
+
+    /// Take an authority withdrawal out of the dev bucket
+    pub fn withdraw_dev(&mut self, amount: u64) -> Result<()> {
+        require!(
+            amount <= self.withdrawable_dev_balance(),
+            RpgError::InsufficientTreasuryFunds
+        );
+        self.bucket_balances[REVENUE_BUCKET_DEV] -= amount;
+        self.record_withdrawal(amount);
+        Ok(())
+    }
+
+    /// Start tracking a mint, a no-op if it is already tracked
+    pub fn register_mint(&mut self, mint: Pubkey) -> Result<()> {
+        if self.mint_revenue(&mint).is_some() {
+            return Ok(());
+        }
+        let slot = self
+            .token_revenue
+            .iter_mut()
+            .find(|revenue| revenue.mint == Pubkey::default())
+            .ok_or(RpgError::TooManyPaymentMints)?;
+        slot.mint = mint;
+        Ok(())
+    }
+
+    pub fn mint_revenue(&self, mint: &Pubkey) -> Option<&MintRevenue> {
+        self.token_revenue
+            .iter()
+            .find(|revenue| revenue.mint == *mint && *mint != Pubkey::default())
+    }
+
+    pub fn add_token_revenue(&mut self, mint: &Pubkey, amount: u64, revenue_type: RevenueType) -> Result<()> {
+        let revenue = self.mint_revenue_mut(mint)?;
+        revenue.total_collected = revenue.total_collected.saturating_add(amount);
+        let sales = self.sales_mut(revenue_type);
+        *sales = sales.saturating_add(1);
+        Ok(())
+    }
+
+    /// Hold a token entry fee until the session resolves or is cancelled, like `escrow_entry`
+    pub fn escrow_token_entry(&mut self, mint: &Pubkey, amount: u64) -> Result<()> {
+        let revenue = self.mint_revenue_mut(mint)?;
+        revenue.escrow = revenue.escrow.saturating_add(amount);
+        Ok(())
+    }
+
+    pub fn release_token_entry(&mut self, mint: &Pubkey, amount: u64, revenue_type: RevenueType) -> Result<()> {
+        let revenue = self.mint_revenue_mut(mint)?;
+        revenue.escrow = revenue.escrow.saturating_sub(amount);
+        self.add_token_revenue(mint, amount, revenue_type)
+    }
+
+    pub fn refund_token_entry(&mut self, mint: &Pubkey, amount: u64) -> Result<()> {
+        let revenue = self.mint_revenue_mut(mint)?;
+        revenue.escrow = revenue.escrow.saturating_sub(amount);
+        Ok(())
+    }
+
+    pub fn record_token_withdrawal(&mut self, mint: &Pubkey, amount: u64) -> Result<()> {
+        let revenue = self.mint_revenue_mut(mint)?;
+        revenue.total_withdrawn = revenue.total_withdrawn.saturating_add(amount);
+        Ok(())
+    }
+
+    fn mint_revenue_mut(&mut self, mint: &Pubkey) -> Result<&mut MintRevenue> {
+        self.token_revenue
+            .iter_mut()
+            .find(|revenue| revenue.mint == *mint && *mint != Pubkey::default())
+            .ok_or(RpgError::InvalidPaymentMint.into())
+    }
+
+    /// Sales counted for each revenue type, shared by SOL and token payments
+    fn sales_mut(&mut self, revenue_type: RevenueType) -> &mut u64 {
         match revenue_type {
-            RevenueType::Battle => {
-                self.total_battles = self.total_battles.saturating_add(1);
-            }
-            RevenueType::Roll => {
-                self.total_rolls = self.total_rolls.saturating_add(1);
-            }
-            RevenueType::Hero => {
-                self.total_heroes_created = self.total_heroes_created.saturating_add(1);
-            }
+            RevenueType::Battle => &mut self.total_battles,
+            RevenueType::Roll => &mut self.total_rolls,
+            RevenueType::Hero => &mut self.total_heroes_created,
         }
     }
 }
@@ -42,4 +289,274 @@ pub enum RevenueType {
     Battle,
     Roll,
     Hero,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_treasury() -> Treasury {
+        Treasury {
+            authority: Pubkey::new_unique(),
+            total_collected: 0,
+            total_battles: 0,
+            total_rolls: 0,
+            total_heroes_created: 0,
+            total_withdrawn: 0,
+            reserve: 0,
+            vault_bump: 255,
+            token_revenue: [MintRevenue::default(); MAX_PAYMENT_MINTS],
+            revenue_shares: [REVENUE_SHARE_TOTAL, 0, 0, 0],
+            bucket_recipients: [Pubkey::default(); REVENUE_BUCKET_COUNT],
+            bucket_balances: [0; REVENUE_BUCKET_COUNT],
+            bucket_claimed: [0; REVENUE_BUCKET_COUNT],
+            referral_owed: 0,
+            pending_escrow: 0,
+            created_at: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_migrate_legacy_layout() {
+        let authority = Pubkey::new_unique();
+        let mut legacy = Treasury::DISCRIMINATOR.to_vec();
+        legacy.extend_from_slice(authority.as_ref());
+        for value in [5_000u64, 3, 2, 1] {
+            legacy.extend_from_slice(&value.to_le_bytes());
+        }
+        legacy.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        legacy.push(254);
+        assert_eq!(legacy.len(), LegacyTreasury::LEN);
+
+        let treasury = Treasury::from_legacy(&legacy, 253, 4_000).unwrap();
+        assert_eq!(treasury.authority, authority);
+        assert_eq!(treasury.total_collected, 5_000);
+        assert_eq!(treasury.total_battles, 3);
+        assert_eq!(treasury.total_rolls, 2);
+        assert_eq!(treasury.total_heroes_created, 1);
+        assert_eq!(treasury.created_at, 1_700_000_000);
+        assert_eq!(treasury.bump, 254);
+        assert_eq!(treasury.vault_bump, 253);
+        assert_eq!(treasury.reserve, TREASURY_RESERVE);
+        assert_eq!(treasury.bucket_recipients, [authority; REVENUE_BUCKET_COUNT]);
+        assert_eq!(treasury.bucket_balances, [4_000, 0, 0, 0]);
+
+        // The rebuilt account fills the new size and reads back as a Treasury
+        let mut data = Vec::new();
+        treasury.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), Treasury::LEN);
+        let migrated = Treasury::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.authority, authority);
+        assert_eq!(migrated.bucket_balances, [4_000, 0, 0, 0]);
+
+        // Already migrated or foreign accounts are rejected
+        assert!(matches!(
+            Treasury::from_legacy(&data, 253, 0),
+            Err(error) if error == RpgError::InvalidTreasuryLayout.into()
+        ));
+        legacy[0] ^= 1;
+        assert!(matches!(
+            Treasury::from_legacy(&legacy, 253, 0),
+            Err(error) if error == RpgError::InvalidTreasuryLayout.into()
+        ));
+    }
+
+    #[test]
+    fn test_revenue_per_mint() {
+        let mut treasury = empty_treasury();
+        let mint = Pubkey::new_unique();
+        let other_mint = Pubkey::new_unique();
+
+        // Unregistered mints can't be paid in
+        assert_eq!(
+            treasury.add_token_revenue(&mint, 10, RevenueType::Roll).unwrap_err(),
+            RpgError::InvalidPaymentMint.into()
+        );
+
+        treasury.register_mint(mint).unwrap();
+        treasury.register_mint(mint).unwrap();
+        treasury.register_mint(other_mint).unwrap();
+        treasury.add_token_revenue(&mint, 10, RevenueType::Roll).unwrap();
+        treasury.add_token_revenue(&mint, 5, RevenueType::Battle).unwrap();
+        treasury.add_token_revenue(&other_mint, 7, RevenueType::Roll).unwrap();
+        treasury.add_revenue(100, RevenueType::Roll, None);
+
+        // Each mint is tracked apart from lamports, sales are counted across currencies
+        assert_eq!(treasury.mint_revenue(&mint).unwrap().total_collected, 15);
+        assert_eq!(treasury.mint_revenue(&other_mint).unwrap().total_collected, 7);
+        assert_eq!(treasury.total_collected, 100);
+        assert_eq!(treasury.total_rolls, 3);
+        assert_eq!(treasury.total_battles, 1);
+
+        // Entry fees wait in escrow, only resolved ones count as revenue
+        treasury.escrow_token_entry(&mint, 20).unwrap();
+        treasury.escrow_token_entry(&mint, 30).unwrap();
+        treasury.release_token_entry(&mint, 20, RevenueType::Battle).unwrap();
+        treasury.refund_token_entry(&mint, 30).unwrap();
+        let revenue = treasury.mint_revenue(&mint).unwrap();
+        assert_eq!(revenue.total_collected, 35);
+        assert_eq!(revenue.escrow, 0);
+        assert_eq!(treasury.total_battles, 2);
+        assert!(treasury.escrow_token_entry(&Pubkey::new_unique(), 10).is_err());
+
+        // Slots run out after MAX_PAYMENT_MINTS mints
+        for _ in 2..MAX_PAYMENT_MINTS {
+            treasury.register_mint(Pubkey::new_unique()).unwrap();
+        }
+        assert_eq!(
+            treasury.register_mint(Pubkey::new_unique()).unwrap_err(),
+            RpgError::TooManyPaymentMints.into()
+        );
+        assert!(treasury.mint_revenue(&Pubkey::default()).is_none());
+    }
+
+    #[test]
+    fn test_revenue_buckets() {
+        let mut treasury = empty_treasury();
+        treasury.revenue_shares = [5000, 3000, 1500, 500];
+
+        treasury.add_revenue(1000, RevenueType::Roll, None);
+        treasury.add_revenue(2000, RevenueType::Battle, None);
+        assert_eq!(treasury.bucket_balances, [1500, 900, 450, 150]);
+        assert_eq!(treasury.allocated_balance(), 3000);
+
+        // Claiming empties only that bucket
+        assert_eq!(treasury.claim_bucket(REVENUE_BUCKET_PRIZE_POOL).unwrap(), 900);
+        assert_eq!(treasury.bucket_balances, [1500, 0, 450, 150]);
+        assert_eq!(treasury.bucket_claimed[REVENUE_BUCKET_PRIZE_POOL], 900);
+        assert_eq!(
+            treasury.claim_bucket(REVENUE_BUCKET_PRIZE_POOL).unwrap_err(),
+            RpgError::NothingToClaim.into()
+        );
+        assert!(treasury.claim_bucket(REVENUE_BUCKET_COUNT).is_err());
+    }
+
+    #[test]
+    fn test_entry_escrow() {
+        let mut treasury = empty_treasury();
+        treasury.revenue_shares = [5000, 3000, 1500, 500];
+
+        // Pending entries are owed back to the player, not to any bucket
+        treasury.escrow_entry(1000);
+        treasury.escrow_entry(2000);
+        assert_eq!(treasury.bucket_balances, [0; REVENUE_BUCKET_COUNT]);
+        assert_eq!(treasury.allocated_balance(), 3000);
+        assert_eq!(treasury.total_rolls, 0);
+
+        // Resolving splits the fee with the split current at that time
+        treasury.release_entry(2000, RevenueType::Battle, None);
+        assert_eq!(treasury.bucket_balances, [1000, 600, 300, 100]);
+        assert_eq!(treasury.pending_escrow, 1000);
+        assert_eq!(treasury.total_battles, 1);
+
+        // A split change and a claim don't affect what a refund gets back
+        treasury.revenue_shares = [REVENUE_SHARE_TOTAL, 0, 0, 0];
+        treasury.claim_bucket(REVENUE_BUCKET_DEV).unwrap();
+        treasury.refund_entry(1000);
+        assert_eq!(treasury.bucket_balances, [0, 600, 300, 100]);
+        assert_eq!(treasury.pending_escrow, 0);
+        assert_eq!(treasury.allocated_balance(), 1000);
+        assert_eq!(treasury.total_collected, 2000);
+    }
+
+    #[test]
+    fn test_reserve_counts_pending_escrow() {
+        let mut treasury = empty_treasury();
+        treasury.reserve = 1000;
+        treasury.add_revenue(1500, RevenueType::Roll, None);
+        assert_eq!(treasury.reserve_excess(), 1000);
+        assert_eq!(treasury.withdrawable_dev_balance(), 500);
+
+        treasury.escrow_entry(400);
+        assert_eq!(treasury.reserve_excess(), 600);
+        assert_eq!(treasury.withdrawable_dev_balance(), 900);
+
+        // Escrow beyond the reserve is still kept, it is part of the allocated balance
+        treasury.escrow_entry(1000);
+        assert_eq!(treasury.reserve_excess(), 0);
+        assert_eq!(treasury.withdrawable_dev_balance(), 1500);
+        assert_eq!(treasury.allocated_balance(), 2900);
+    }
+
+    #[test]
+    fn test_withdraw_from_dev_bucket() {
+        let mut treasury = empty_treasury();
+        treasury.revenue_shares = [5000, 5000, 0, 0];
+        treasury.reserve = 300;
+        treasury.add_revenue(2000, RevenueType::Battle, None);
+
+        // Only the dev bucket pays withdrawals, less the reserve it holds back
+        assert_eq!(
+            treasury.withdraw_dev(701).unwrap_err(),
+            RpgError::InsufficientTreasuryFunds.into()
+        );
+        treasury.withdraw_dev(200).unwrap();
+        assert_eq!(treasury.bucket_balances, [800, 1000, 0, 0]);
+        assert_eq!(treasury.total_withdrawn, 200);
+
+        // Claims hold back the same reserve
+        assert_eq!(treasury.claim_bucket(REVENUE_BUCKET_DEV).unwrap(), 500);
+        assert_eq!(treasury.bucket_balances, [300, 1000, 0, 0]);
+        assert_eq!(
+            treasury.claim_bucket(REVENUE_BUCKET_DEV).unwrap_err(),
+            RpgError::NothingToClaim.into()
+        );
+        assert!(treasury.withdraw_dev(1).is_err());
+    }
+
+    #[test]
+    fn test_referral_rewards() {
+        let mut treasury = empty_treasury();
+        treasury.revenue_shares = [8000, 1000, 1000, 0];
+        let mut referrer = Player {
+            authority: Pubkey::new_unique(),
+            hero_count: 0,
+            total_battles: 0,
+            total_victories: 0,
+            total_xp_earned: 0,
+            total_sol_spent: 0,
+            gold: 0,
+            total_gold_earned: 0,
+            total_gold_spent: 0,
+            referrer: None,
+            referral_count: 1,
+            referee_sol_spent: 0,
+            referral_balance: 0,
+            total_referral_earned: 0,
+            created_at: 0,
+            bump: 255,
+        };
+
+        // With the default split the referral pool is empty, the reward comes off the top
+        treasury.revenue_shares = [REVENUE_SHARE_TOTAL, 0, 0, 0];
+        treasury.add_revenue(10_000, RevenueType::Roll, Some((&mut referrer, 500)));
+        assert_eq!(treasury.bucket_balances, [9_500, 0, 0, 0]);
+        assert_eq!(treasury.referral_owed, 500);
+        assert_eq!(treasury.total_collected, 10_000);
+        assert_eq!(referrer.referral_balance, 500);
+        assert_eq!(referrer.referee_sol_spent, 10_000);
+        assert_eq!(treasury.allocated_balance(), 10_000);
+
+        // Released entries pay the referrer the same way, the rest is split
+        treasury.revenue_shares = [8000, 1000, 1000, 0];
+        treasury.escrow_entry(1_000);
+        treasury.release_entry(1_000, RevenueType::Battle, Some((&mut referrer, 50)));
+        assert_eq!(treasury.bucket_balances, [10_260, 95, 95, 0]);
+        assert_eq!(treasury.pending_escrow, 0);
+        assert_eq!(referrer.referral_balance, 550);
+
+        // Spend that earns nothing isn't recorded, rewards never exceed the fee
+        treasury.add_revenue(10, RevenueType::Roll, Some((&mut referrer, 0)));
+        assert_eq!(referrer.referee_sol_spent, 11_000);
+        treasury.add_revenue(100, RevenueType::Roll, Some((&mut referrer, 1_000)));
+        assert_eq!(referrer.referral_balance, 650);
+        assert_eq!(treasury.allocated_balance(), treasury.total_collected);
+
+        let claimed = referrer.claim_referral_balance().unwrap();
+        treasury.pay_referral(claimed);
+        assert_eq!(treasury.referral_owed, 0);
+        assert_eq!(referrer.total_referral_earned, 650);
+        assert!(referrer.claim_referral_balance().is_err());
+    }
+}
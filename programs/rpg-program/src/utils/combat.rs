@@ -0,0 +1,456 @@
+//! Deterministic combat resolution
+//! The program and off-chain replay tools share these functions, so a battle's
+//! outcome can be recomputed from its seed, attribute and config snapshots and action log.
+
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::{Attributes, Battle, CombatConfig, ElementalProfile, OracleLayout};
+use crate::utils::math::*;
+use crate::utils::rng::*;
+
+/// A single action taken during a turn, in execution order
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CombatAction {
+    pub actor: u8, // COMBATANT_HERO or COMBATANT_ENEMY
+    pub action: u8,
+    pub damage: u32,
+}
+
+/// Damage dealt by each side during a single turn, plus the ordered action sequence
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TurnResult {
+    pub hero_damage: u32,
+    pub enemy_damage: u32,
+    pub sequence: Vec<CombatAction>,
+}
+
+/// Resolve one turn against the battle's attribute snapshots, then log it and advance the turn
+pub fn resolve_turn(battle: &mut Battle, action: u8) -> Result<TurnResult> {
+    require!(battle.is_active(), RpgError::BattleAlreadyCompleted);
+    require!(battle.turn < u8::MAX, RpgError::BattleTurnLimitReached);
+
+    let hero_attrs = battle.hero_attributes;
+    let enemy_attrs = battle.enemy_attributes;
+
+    // Determine turn order (higher agility goes first)
+    let hero_goes_first = calculate_battle_priority(hero_attrs.agility, enemy_attrs.agility);
+    let (first, second) = if hero_goes_first {
+        (COMBATANT_HERO, COMBATANT_ENEMY)
+    } else {
+        (COMBATANT_ENEMY, COMBATANT_HERO)
+    };
+
+    // Fill speed gauges from dexterity, a full gauge grants an extra action this turn
+    let hero_gain = calculate_speed_gain(&battle.combat_config, hero_attrs.dexterity, enemy_attrs.dexterity);
+    let enemy_gain = calculate_speed_gain(&battle.combat_config, enemy_attrs.dexterity, hero_attrs.dexterity);
+    let (hero_extra, enemy_extra) = battle.fill_speed_gauges(hero_gain, enemy_gain);
+
+    let mut actors = vec![first, second];
+    if hero_extra {
+        actors.push(COMBATANT_HERO);
+    }
+    if enemy_extra {
+        actors.push(COMBATANT_ENEMY);
+    }
+
+    let mut result = TurnResult::default();
+    let mut rng = RngStream::new(&battle.turn_seed(), battle.turn);
+
+    for actor in actors {
+        // Nobody acts once the battle is decided
+        if !battle.is_active() {
+            break;
+        }
+
+        if actor == COMBATANT_HERO {
+            let damage = execute_hero_action(action, &hero_attrs, &enemy_attrs, battle, &mut rng)?.unwrap_or(0);
+            battle.damage_enemy(damage);
+            result.hero_damage = result.hero_damage.saturating_add(damage);
+            result.sequence.push(CombatAction { actor, action, damage });
+        } else {
+            let damage = execute_enemy_action(&enemy_attrs, &hero_attrs, battle);
+            battle.damage_hero(damage);
+            result.enemy_damage = result.enemy_damage.saturating_add(damage);
+            result.sequence.push(CombatAction { actor, action: ACTION_ATTACK, damage });
+        }
+    }
+
+    battle.record_turn(action, result.hero_damage, result.enemy_damage);
+    battle.next_turn();
+
+    Ok(result)
+}
+
+pub fn execute_hero_action(
+    action: u8,
+    hero_attrs: &Attributes,
+    enemy_attrs: &Attributes,
+    battle: &mut Battle,
+    rng: &mut RngStream,
+) -> Result<Option<u32>> {
+    match action {
+        ACTION_ATTACK => {
+            // Physical attacks are neutral
+            let damage = calculate_damage(
+                &battle.combat_config,
+                hero_attrs.strength,
+                enemy_attrs.vitality,
+                ELEMENT_NEUTRAL,
+                battle.enemy_elemental.element,
+                battle.enemy_elemental.resistance(ELEMENT_NEUTRAL),
+            );
+
+            // Check for critical hit
+            let crit_chance = (hero_attrs.precision as u32) * 100; // Convert to basis points
+            if rng.check(RNG_DOMAIN_CRIT, crit_chance as u16) {
+                let crit_damage = calculate_critical_damage(&battle.combat_config, damage);
+                Ok(Some(crit_damage))
+            } else {
+                Ok(Some(damage))
+            }
+        }
+        ACTION_DEFEND => {
+            // Defending reduces incoming damage by 50% this turn
+            // This is handled in the enemy attack calculation
+            Ok(None)
+        }
+        ACTION_SKILL => execute_class_skill(hero_attrs, enemy_attrs, battle, rng),
+        ACTION_ESCAPE => {
+            let escape_chance = calculate_escape_chance(&battle.combat_config, hero_attrs.agility, enemy_attrs.agility);
+            if rng.check(RNG_DOMAIN_ESCAPE, escape_chance) {
+                battle.escape_battle();
+            }
+            Ok(None)
+        }
+        _ => Err(RpgError::InvalidBattleAction.into()),
+    }
+}
+
+/// Class-exclusive skill, elemental skills use the hero's skill element
+fn execute_class_skill(
+    hero_attrs: &Attributes,
+    enemy_attrs: &Attributes,
+    battle: &mut Battle,
+    rng: &mut RngStream,
+) -> Result<Option<u32>> {
+    let skill_element = battle.hero_elemental.element;
+    let enemy_element = battle.enemy_elemental.element;
+    let enemy_resistance = battle.enemy_elemental.resistance(skill_element);
+
+    match battle.hero_class {
+        HERO_CLASS_WARRIOR => {
+            // Power Strike: heavy strength attack
+            let damage = calculate_damage(
+                &battle.combat_config,
+                hero_attrs.strength,
+                enemy_attrs.vitality,
+                skill_element,
+                enemy_element,
+                enemy_resistance,
+            );
+            Ok(Some(damage.saturating_mul(POWER_STRIKE_MULTIPLIER) / 10000))
+        }
+        HERO_CLASS_ROGUE => {
+            // Backstab: dexterity attack with a much higher crit chance
+            let damage = calculate_damage(
+                &battle.combat_config,
+                hero_attrs.dexterity,
+                enemy_attrs.vitality,
+                skill_element,
+                enemy_element,
+                enemy_resistance,
+            );
+            let crit_chance = std::cmp::min(
+                (hero_attrs.precision as u32) * BACKSTAB_CRIT_MULTIPLIER,
+                10000,
+            );
+            if rng.check(RNG_DOMAIN_CRIT, crit_chance as u16) {
+                Ok(Some(calculate_critical_damage(&battle.combat_config, damage)))
+            } else {
+                Ok(Some(damage))
+            }
+        }
+        HERO_CLASS_CLERIC => {
+            // Heal: restore HP based on wisdom
+            battle.heal_hero((hero_attrs.wisdom as u32).saturating_mul(HEAL_WISDOM_MULTIPLIER));
+            Ok(None)
+        }
+        _ => {
+            // Mage (and fallback): magic attack using intelligence
+            let damage = calculate_damage(
+                &battle.combat_config,
+                hero_attrs.intelligence,
+                enemy_attrs.wisdom,
+                skill_element,
+                enemy_element,
+                enemy_resistance,
+            );
+            Ok(Some(damage))
+        }
+    }
+}
+
+pub fn execute_enemy_action(
+    enemy_attrs: &Attributes,
+    hero_attrs: &Attributes,
+    battle: &Battle,
+) -> u32 {
+    let ai_flags = battle.enemy_ai_flags;
+
+    // Enemy attacks carry the enemy's element, heroes themselves are neutral
+    let enemy_element = battle.enemy_elemental.element;
+    let damage = calculate_damage(
+        &battle.combat_config,
+        enemy_attrs.strength,
+        hero_attrs.vitality,
+        enemy_element,
+        ELEMENT_NEUTRAL,
+        battle.hero_elemental.resistance(enemy_element),
+    );
+
+    // Simple AI: aggressive enemies always attack, defensive enemies defend if low HP
+    if ai_flags & AI_AGGRESSIVE != 0 {
+        damage
+    } else if ai_flags & AI_DEFENSIVE != 0 && battle.enemy_hp < battle.enemy_max_hp / 3 {
+        // Defend when low on HP
+        damage / 2
+    } else {
+        // Balanced or normal attack
+        damage
+    }
+}
+
+/// Inputs needed to replay a battle from scratch
+#[derive(Clone, Copy, Debug)]
+pub struct ReplayInput<'a> {
+    pub rng_seed: [u8; 32],
+    pub hero_class: u8,
+    pub hero_attributes: Attributes,
+    pub enemy_attributes: Attributes,
+    pub hero_elemental: ElementalProfile,
+    pub enemy_elemental: ElementalProfile,
+    pub enemy_ai_flags: u8,
+    pub combat_config: CombatConfig,
+    pub hero_max_hp: u32,
+    pub enemy_max_hp: u32,
+    pub actions: &'a [u8],
+    pub turn_entropy: &'a [[u8; 32]], // One slot hash per action in per-turn mode, empty otherwise
+}
+
+/// Replay a sequence of hero actions and return the resulting battle state
+pub fn replay_battle(input: &ReplayInput) -> Result<Battle> {
+    let mut battle = Battle {
+        hero: Pubkey::default(),
+        enemy_template: Pubkey::default(),
+        hero_hp: input.hero_max_hp,
+        enemy_hp: input.enemy_max_hp,
+        hero_max_hp: input.hero_max_hp,
+        enemy_max_hp: input.enemy_max_hp,
+        hero_class: input.hero_class,
+        hero_attributes: input.hero_attributes,
+        enemy_attributes: input.enemy_attributes,
+        hero_elemental: input.hero_elemental,
+        enemy_elemental: input.enemy_elemental,
+        enemy_ai_flags: input.enemy_ai_flags,
+        combat_config: input.combat_config,
+        hero_speed_gauge: 0,
+        enemy_speed_gauge: 0,
+        rng_seed: input.rng_seed,
+        per_turn_randomness: false,
+        committed_action: None,
+        action_commit_slot: 0,
+        turn_entropy: [0u8; 32],
+        turn: 0,
+        state: BATTLE_STATE_ACTIVE,
+        action_log: [0u8; BATTLE_ACTION_LOG_LEN],
+        log_hash: [0u8; 32],
+        nonce: 0,
+        commit_slot: 0,
+        recommit_count: 0,
+        randomness_provider: RANDOMNESS_PROVIDER_SLOT_HASHES,
+        oracle_program: Pubkey::default(),
+        secret_hash: [0u8; 32],
+        entry_cost: 0,
+        payment_mint: None,
+        referral_reward: 0,
+        created_at: 0,
+        settled: false,
+        settled_at: 0,
+        bump: 0,
+        oracle_layout: OracleLayout::default(),
+    };
+
+    battle.per_turn_randomness = !input.turn_entropy.is_empty();
+    require!(
+        !battle.per_turn_randomness || input.turn_entropy.len() == input.actions.len(),
+        RpgError::InvalidBattleAction
+    );
+
+    for (turn, &action) in input.actions.iter().enumerate() {
+        if battle.per_turn_randomness {
+            battle.turn_entropy = input.turn_entropy[turn];
+        }
+        resolve_turn(&mut battle, action)?;
+    }
+
+    Ok(battle)
+}
+
+/// Check that a recorded battle matches a replay of its own seed, snapshots and action log.
+/// Per-turn battles also need the entropy of every turn, as emitted by `battle_turn`.
+pub fn verify_battle(battle: &Battle, turn_entropy: &[[u8; 32]]) -> Result<bool> {
+    let actions = battle.logged_actions();
+    let replayed = replay_battle(&ReplayInput {
+        rng_seed: battle.rng_seed,
+        hero_class: battle.hero_class,
+        hero_attributes: battle.hero_attributes,
+        enemy_attributes: battle.enemy_attributes,
+        hero_elemental: battle.hero_elemental,
+        enemy_elemental: battle.enemy_elemental,
+        enemy_ai_flags: battle.enemy_ai_flags,
+        combat_config: battle.combat_config,
+        hero_max_hp: battle.hero_max_hp,
+        enemy_max_hp: battle.enemy_max_hp,
+        actions: &actions,
+        turn_entropy,
+    })?;
+
+    Ok(replayed.hero_hp == battle.hero_hp
+        && replayed.enemy_hp == battle.enemy_hp
+        && replayed.state == battle.state
+        && replayed.turn == battle.turn
+        && replayed.log_hash == battle.log_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_input(actions: &[u8]) -> ReplayInput<'_> {
+        let hero = Attributes::new_base();
+        let mut enemy = Attributes::new_base();
+        enemy.strength = 12;
+        enemy.vitality = 8;
+
+        ReplayInput {
+            rng_seed: [42u8; 32],
+            hero_class: HERO_CLASS_MAGE,
+            hero_attributes: hero,
+            enemy_attributes: enemy,
+            hero_elemental: ElementalProfile::default(),
+            enemy_elemental: ElementalProfile {
+                element: ELEMENT_EARTH,
+                resistances: [0, 0, 0, 2500, 0, 0, 0],
+            },
+            enemy_ai_flags: AI_AGGRESSIVE,
+            combat_config: CombatConfig::default(),
+            hero_max_hp: calculate_hp(&CombatConfig::default(), hero.vitality),
+            enemy_max_hp: calculate_hp(&CombatConfig::default(), enemy.vitality),
+            actions,
+            turn_entropy: &[],
+        }
+    }
+
+    #[test]
+    fn test_replay_is_deterministic() {
+        let actions = [ACTION_ATTACK, ACTION_SKILL, ACTION_ATTACK];
+        let first = replay_battle(&test_input(&actions)).unwrap();
+        let second = replay_battle(&test_input(&actions)).unwrap();
+
+        assert_eq!(first.hero_hp, second.hero_hp);
+        assert_eq!(first.enemy_hp, second.enemy_hp);
+        assert_eq!(first.log_hash, second.log_hash);
+        assert_eq!(first.logged_actions(), actions.to_vec());
+    }
+
+    #[test]
+    fn test_verify_battle() {
+        let actions = [ACTION_ATTACK, ACTION_DEFEND, ACTION_SKILL];
+        let mut battle = replay_battle(&test_input(&actions)).unwrap();
+        assert!(verify_battle(&battle, &[]).unwrap());
+
+        // Tampering with the result is detected
+        battle.enemy_hp = battle.enemy_hp.saturating_sub(1);
+        assert!(!verify_battle(&battle, &[]).unwrap());
+    }
+
+    #[test]
+    fn test_per_turn_entropy() {
+        let actions = [ACTION_ATTACK, ACTION_ATTACK, ACTION_ATTACK];
+        let entropy = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        let input = ReplayInput { turn_entropy: &entropy, ..test_input(&actions) };
+
+        let battle = replay_battle(&input).unwrap();
+        assert!(verify_battle(&battle, &entropy).unwrap());
+
+        // Every turn depends on its own entropy, so it must be supplied in full
+        let other = [[1u8; 32], [2u8; 32], [4u8; 32]];
+        assert!(!verify_battle(&battle, &other).unwrap());
+        assert!(verify_battle(&battle, &entropy[..2]).is_err());
+    }
+
+    #[test]
+    fn test_replay_stops_when_battle_ends() {
+        // The enemy dies long before 100 attacks, so the replay must fail
+        let actions = [ACTION_ATTACK; 100];
+        assert!(replay_battle(&test_input(&actions)).is_err());
+    }
+
+    #[test]
+    fn test_class_skills() {
+        let actions = [ACTION_SKILL];
+
+        // Warrior's Power Strike damages the enemy
+        let mut input = test_input(&actions);
+        input.hero_class = HERO_CLASS_WARRIOR;
+        let warrior = replay_battle(&input).unwrap();
+        assert!(warrior.enemy_hp < warrior.enemy_max_hp);
+
+        // Cleric deals no damage and never overheals
+        input.hero_class = HERO_CLASS_CLERIC;
+        let cleric = replay_battle(&input).unwrap();
+        assert_eq!(cleric.enemy_hp, cleric.enemy_max_hp);
+        assert!(cleric.hero_hp <= cleric.hero_max_hp);
+
+        // Cleric heals to full before the enemy's next hit, ending where one turn left off
+        let actions = [ACTION_DEFEND, ACTION_SKILL];
+        let mut input = test_input(&actions);
+        input.hero_class = HERO_CLASS_CLERIC;
+        let healed = replay_battle(&input).unwrap();
+        let defended = replay_battle(&ReplayInput { actions: &actions[..1], ..input }).unwrap();
+        assert!(defended.hero_hp < defended.hero_max_hp);
+        assert_eq!(healed.hero_hp, defended.hero_hp);
+    }
+
+    #[test]
+    fn test_speed_extra_actions() {
+        // Twice the enemy's dexterity acts twice every turn
+        let mut battle = replay_battle(&test_input(&[])).unwrap();
+        battle.hero_attributes.dexterity = 20;
+        battle.enemy_attributes.dexterity = 10;
+
+        let result = resolve_turn(&mut battle, ACTION_DEFEND).unwrap();
+        let actors: Vec<u8> = result.sequence.iter().map(|a| a.actor).collect();
+        assert_eq!(actors, vec![COMBATANT_HERO, COMBATANT_ENEMY, COMBATANT_HERO]);
+
+        // 50% faster acts twice every other turn
+        let mut battle = replay_battle(&test_input(&[])).unwrap();
+        battle.hero_attributes.dexterity = 15;
+        battle.enemy_attributes.dexterity = 10;
+
+        let first = resolve_turn(&mut battle, ACTION_DEFEND).unwrap();
+        let second = resolve_turn(&mut battle, ACTION_DEFEND).unwrap();
+        assert_eq!(first.sequence.len(), 2);
+        assert_eq!(second.sequence.len(), 3);
+
+        // Damage totals match the sequence
+        let total: u32 = second
+            .sequence
+            .iter()
+            .filter(|a| a.actor == COMBATANT_ENEMY)
+            .map(|a| a.damage)
+            .sum();
+        assert_eq!(total, second.enemy_damage);
+    }
+}
\ No newline at end of file
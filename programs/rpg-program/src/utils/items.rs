@@ -0,0 +1,103 @@
+//! Account creation for items awarded by battles and rolls or bought in the shop
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::{Hero, Item};
+
+/// Create the hero's next item PDA, derived from the hero and its item count, and write `item` to it
+pub fn create_item<'info>(
+    payer: &AccountInfo<'info>,
+    item_account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    program_id: &Pubkey,
+    hero: &mut Account<'info, Hero>,
+    mut item: Item,
+) -> Result<Pubkey> {
+    let hero_key = hero.key();
+    let item_index = hero.next_item_index().to_le_bytes();
+    let (item_key, item_bump) =
+        Pubkey::find_program_address(&[ITEM_SEED, hero_key.as_ref(), &item_index], program_id);
+    require_keys_eq!(item_account.key(), item_key, RpgError::InvalidLootItemAccount);
+
+    create_pda_account(
+        payer,
+        item_account,
+        system_program,
+        program_id,
+        &[ITEM_SEED, hero_key.as_ref(), &item_index, &[item_bump]],
+        Item::LEN,
+    )?;
+
+    item.bump = item_bump;
+    let mut data = item_account.try_borrow_mut_data()?;
+    item.try_serialize(&mut &mut data[..])?;
+
+    Ok(item_key)
+}
+
+/// Create a program-owned PDA, tolerating lamports already sent to the address
+pub fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    new_account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    owner: &Pubkey,
+    signer_seeds: &[&[u8]],
+    space: usize,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let current_lamports = new_account.lamports();
+
+    if current_lamports == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: new_account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            rent,
+            space as u64,
+            owner,
+        )?;
+    } else {
+        let top_up = rent.saturating_sub(current_lamports);
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    system_program::Transfer {
+                        from: payer.clone(),
+                        to: new_account.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::Allocate {
+                    account_to_allocate: new_account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            space as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::Assign {
+                    account_to_assign: new_account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            owner,
+        )?;
+    }
+
+    Ok(())
+}
@@ -1,43 +1,110 @@
-use crate::state::Attributes;
+use crate::state::{Attributes, CombatConfig, EconomyConfig};
 use crate::constants::*;
 
-/// Calculate damage with defense reduction
-pub fn calculate_damage(attack: u16, defense: u16) -> u32 {
+/// Calculate damage with defense reduction, elemental advantage and resistance
+pub fn calculate_damage(
+    config: &CombatConfig,
+    attack: u16,
+    defense: u16,
+    attack_element: u8,
+    defender_element: u8,
+    defender_resistance: i16,
+) -> u32 {
     let base_damage = attack as u32;
-    let damage_reduction = (defense as u32) / (BASE_DAMAGE_REDUCTION as u32);
-    std::cmp::max(1, base_damage.saturating_sub(damage_reduction))
+    let damage_reduction = (defense as u32) / std::cmp::max(config.base_damage_reduction, 1) as u32;
+    let damage = std::cmp::max(1, base_damage.saturating_sub(damage_reduction));
+
+    // Apply elemental advantage, then the defender's resistance to that element
+    let element_multiplier = calculate_element_multiplier(config, attack_element, defender_element);
+    let damage = damage.saturating_mul(element_multiplier) / 10000;
+    let resistance = defender_resistance.clamp(MIN_ELEMENT_RESISTANCE, MAX_ELEMENT_RESISTANCE);
+    let resistance_multiplier = (10000 - resistance as i32) as u32;
+    std::cmp::max(1, damage.saturating_mul(resistance_multiplier) / 10000)
+}
+
+/// Elemental advantage multiplier (out of 10000)
+/// Water > Fire > Air > Earth > Water, Light and Dark are strong against each other
+pub fn calculate_element_multiplier(config: &CombatConfig, attack_element: u8, defender_element: u8) -> u32 {
+    if element_beats(attack_element, defender_element) {
+        config.element_advantage_multiplier as u32
+    } else if element_beats(defender_element, attack_element) {
+        config.element_disadvantage_multiplier as u32
+    } else {
+        10000
+    }
+}
+
+fn element_beats(attack_element: u8, defender_element: u8) -> bool {
+    matches!(
+        (attack_element, defender_element),
+        (ELEMENT_WATER, ELEMENT_FIRE)
+            | (ELEMENT_FIRE, ELEMENT_AIR)
+            | (ELEMENT_AIR, ELEMENT_EARTH)
+            | (ELEMENT_EARTH, ELEMENT_WATER)
+            | (ELEMENT_LIGHT, ELEMENT_DARK)
+            | (ELEMENT_DARK, ELEMENT_LIGHT)
+    )
 }
 
 /// Calculate critical hit damage
-pub fn calculate_critical_damage(base_damage: u32) -> u32 {
-    base_damage.saturating_mul(CRITICAL_MULTIPLIER as u32)
+pub fn calculate_critical_damage(config: &CombatConfig, base_damage: u32) -> u32 {
+    base_damage.saturating_mul(config.critical_multiplier as u32)
 }
 
 /// Calculate escape chance based on agility difference
-pub fn calculate_escape_chance(hero_agility: u16, enemy_agility: u16) -> u16 {
-    let base_chance = BASE_ESCAPE_CHANCE;
+pub fn calculate_escape_chance(config: &CombatConfig, hero_agility: u16, enemy_agility: u16) -> u16 {
+    let base_chance = config.base_escape_chance;
     
-    let agility_diff = if hero_agility >= enemy_agility {
-        hero_agility - enemy_agility
-    } else {
-        0 // Can't have negative escape chance bonus
-    };
+    // Can't have negative escape chance bonus
+    let agility_diff = hero_agility.saturating_sub(enemy_agility);
     
-    let bonus = (agility_diff as u16).saturating_mul(AGILITY_ESCAPE_BONUS);
+    let bonus = agility_diff.saturating_mul(config.agility_escape_bonus);
     let total_chance = base_chance.saturating_add(bonus);
     
-    // Clamp between 10% and 80%
-    std::cmp::min(std::cmp::max(total_chance, 1000), 8000)
+    // Clamp between the configured bounds (10% and 80% by default)
+    total_chance.clamp(config.min_escape_chance, config.max_escape_chance)
 }
 
 /// Calculate HP from vitality
-pub fn calculate_hp(vitality: u16) -> u32 {
-    (vitality as u32).saturating_mul(BASE_HP_MULTIPLIER as u32)
+pub fn calculate_hp(config: &CombatConfig, vitality: u16) -> u32 {
+    (vitality as u32).saturating_mul(config.base_hp_multiplier as u32)
 }
 
 /// Calculate XP required for a specific level
-pub fn calculate_xp_required(level: u16) -> u32 {
-    XP_PER_LEVEL_BASE.saturating_mul(level as u32 + 1)
+pub fn calculate_xp_required(config: &EconomyConfig, level: u16) -> u32 {
+    config.xp_per_level_base.saturating_mul(level as u32 + 1)
+}
+
+/// Gold awarded for defeating an enemy of `enemy_level`
+pub fn calculate_gold_reward(config: &EconomyConfig, enemy_level: u16) -> u64 {
+    (config.gold_per_enemy_level as u64).saturating_mul(enemy_level as u64)
+}
+
+/// Referrer's cut of a referee's SOL spend, taken off the top of the fee
+pub fn calculate_referral_reward(config: &EconomyConfig, spent: u64) -> u64 {
+    (spent as u128 * config.referral_bps as u128 / REVENUE_SHARE_TOTAL as u128) as u64
+}
+
+/// Gold to restore an item from `durability` to full
+pub fn calculate_repair_cost(config: &EconomyConfig, durability: u16) -> u64 {
+    let missing = MAX_ITEM_DURABILITY.saturating_sub(durability);
+    (config.item_repair_cost as u64).saturating_mul(missing as u64)
+}
+
+/// Gold to upgrade an item from `rarity` to the next rarity
+pub fn calculate_upgrade_cost(config: &EconomyConfig, rarity: u8) -> u64 {
+    config.item_upgrade_cost.saturating_mul(rarity as u64 + 1)
+}
+
+/// Divide `amount` by basis-point shares, rounding dust goes to the reserve bucket
+pub fn split_revenue(amount: u64, shares: &[u16; REVENUE_BUCKET_COUNT]) -> [u64; REVENUE_BUCKET_COUNT] {
+    let mut split = [0u64; REVENUE_BUCKET_COUNT];
+    for (bucket, &share) in split.iter_mut().zip(shares) {
+        *bucket = (amount as u128 * share as u128 / REVENUE_SHARE_TOTAL as u128) as u64;
+    }
+    let allocated: u64 = split.iter().sum();
+    split[REVENUE_BUCKET_RESERVE] += amount - allocated;
+    split
 }
 
 /// Calculate effective attributes including equipment bonuses
@@ -56,46 +123,196 @@ pub fn calculate_battle_priority(hero_agility: u16, enemy_agility: u16) -> bool
     hero_agility >= enemy_agility
 }
 
+/// Speed gauge gained per turn (out of 10000) from a dexterity ("attack speed") surplus.
+/// Twice the opponent's dexterity fills the gauge every turn, so at most one extra action.
+pub fn calculate_speed_gain(config: &CombatConfig, own_dexterity: u16, other_dexterity: u16) -> u16 {
+    let surplus = own_dexterity.saturating_sub(other_dexterity) as u32;
+    let advantage = surplus * 10000 / std::cmp::max(other_dexterity, 1) as u32;
+
+    if advantage < config.min_speed_advantage as u32 {
+        0
+    } else {
+        std::cmp::min(advantage, SPEED_GAUGE_FULL as u32) as u16
+    }
+}
+
+/// Luck modifier (out of 10000) with diminishing returns, each point is worth less than the last
+pub fn calculate_luck_modifier(config: &EconomyConfig, luck: u16) -> u16 {
+    let denominator = luck as u32 + config.luck_half_point as u32;
+    if denominator == 0 {
+        return 0;
+    }
+    let modifier = config.max_luck_bonus as u32 * luck as u32 / denominator;
+    std::cmp::min(modifier, config.max_luck_bonus as u32) as u16
+}
+
+/// Soft pity chance (out of 10000) to lift a miss to the pity floor
+pub fn calculate_soft_pity_bonus(config: &EconomyConfig, pity: u16) -> u16 {
+    if pity < config.pity_soft_start {
+        return 0;
+    }
+    let misses = pity - config.pity_soft_start + 1;
+    std::cmp::min(misses.saturating_mul(config.pity_soft_bonus), 10000)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn combat() -> CombatConfig {
+        CombatConfig::default()
+    }
+
+    fn economy() -> EconomyConfig {
+        EconomyConfig::default()
+    }
+
     #[test]
     fn test_calculate_damage() {
-        let damage = calculate_damage(100, 20);
+        let damage = calculate_damage(&combat(), 100, 20, ELEMENT_NEUTRAL, ELEMENT_NEUTRAL, 0);
         assert_eq!(damage, 90); // 100 - 20/2 = 90
         
-        let min_damage = calculate_damage(5, 20);
+        let min_damage = calculate_damage(&combat(), 5, 20, ELEMENT_NEUTRAL, ELEMENT_NEUTRAL, 0);
         assert_eq!(min_damage, 1); // Always at least 1 damage
     }
 
+    #[test]
+    fn test_calculate_damage_elements() {
+        // Advantage and disadvantage
+        assert_eq!(calculate_damage(&combat(), 100, 20, ELEMENT_WATER, ELEMENT_FIRE, 0), 135); // 90 * 1.5
+        assert_eq!(calculate_damage(&combat(), 100, 20, ELEMENT_FIRE, ELEMENT_WATER, 0), 67); // 90 * 0.75
+        assert_eq!(calculate_damage(&combat(), 100, 20, ELEMENT_LIGHT, ELEMENT_DARK, 0), 135);
+        assert_eq!(calculate_damage(&combat(), 100, 20, ELEMENT_DARK, ELEMENT_LIGHT, 0), 135);
+        assert_eq!(calculate_damage(&combat(), 100, 20, ELEMENT_FIRE, ELEMENT_EARTH, 0), 90);
+
+        // Resistance and weakness
+        assert_eq!(calculate_damage(&combat(), 100, 20, ELEMENT_FIRE, ELEMENT_NEUTRAL, 5000), 45);
+        assert_eq!(calculate_damage(&combat(), 100, 20, ELEMENT_FIRE, ELEMENT_NEUTRAL, -5000), 135);
+
+        // Resistance is capped, damage never drops below 1
+        assert_eq!(calculate_damage(&combat(), 100, 20, ELEMENT_FIRE, ELEMENT_NEUTRAL, i16::MAX), 9);
+        assert_eq!(calculate_damage(&combat(), 5, 20, ELEMENT_FIRE, ELEMENT_WATER, 9000), 1);
+    }
+
     #[test]
     fn test_calculate_critical_damage() {
-        let crit_damage = calculate_critical_damage(50);
+        let crit_damage = calculate_critical_damage(&combat(), 50);
         assert_eq!(crit_damage, 100); // 50 * 2 = 100
     }
 
     #[test]
     fn test_calculate_escape_chance() {
-        let chance = calculate_escape_chance(20, 10);
+        let chance = calculate_escape_chance(&combat(), 20, 10);
         assert!(chance > BASE_ESCAPE_CHANCE); // Should have bonus
         
-        let chance_low = calculate_escape_chance(10, 20);
+        let chance_low = calculate_escape_chance(&combat(), 10, 20);
         assert_eq!(chance_low, BASE_ESCAPE_CHANCE); // No bonus for lower agility
     }
 
     #[test]
     fn test_calculate_hp() {
-        let hp = calculate_hp(50);
+        let hp = calculate_hp(&combat(), 50);
         assert_eq!(hp, 500); // 50 * 10 = 500
     }
 
     #[test]
     fn test_calculate_xp_required() {
-        let xp = calculate_xp_required(0);
+        let xp = calculate_xp_required(&economy(), 0);
         assert_eq!(xp, 1000); // Level 1 requires 1000 XP
         
-        let xp_level_2 = calculate_xp_required(1);
+        let xp_level_2 = calculate_xp_required(&economy(), 1);
         assert_eq!(xp_level_2, 2000); // Level 2 requires 2000 XP
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_split_revenue() {
+        let split = split_revenue(1_000_000, &[5000, 3000, 1500, 500]);
+        assert_eq!(split, [500_000, 300_000, 150_000, 50_000]);
+
+        // Rounding dust lands in the reserve so nothing is lost
+        let split = split_revenue(999, &[3333, 3333, 3334, 0]);
+        assert_eq!(split.iter().sum::<u64>(), 999);
+        assert_eq!(split, [332, 332, 333, 2]);
+
+        assert_eq!(split_revenue(u64::MAX, &[10000, 0, 0, 0]), [u64::MAX, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_calculate_gold_reward() {
+        assert_eq!(calculate_gold_reward(&economy(), 1), 10);
+        assert_eq!(calculate_gold_reward(&economy(), 25), 250);
+        assert_eq!(calculate_gold_reward(&economy(), 0), 0);
+
+        let config = EconomyConfig { gold_per_enemy_level: u32::MAX, ..economy() };
+        assert_eq!(calculate_gold_reward(&config, u16::MAX), u32::MAX as u64 * u16::MAX as u64);
+    }
+
+    #[test]
+    fn test_calculate_referral_reward() {
+        assert_eq!(calculate_referral_reward(&economy(), 1_000_000), 50_000);
+        assert_eq!(calculate_referral_reward(&economy(), 19), 0);
+
+        let config = EconomyConfig { referral_bps: REVENUE_SHARE_TOTAL, ..economy() };
+        assert_eq!(calculate_referral_reward(&config, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_item_costs() {
+        assert_eq!(calculate_repair_cost(&economy(), MAX_ITEM_DURABILITY), 0);
+        assert_eq!(calculate_repair_cost(&economy(), 95), 5);
+        assert_eq!(calculate_repair_cost(&economy(), 0), MAX_ITEM_DURABILITY as u64);
+
+        // Each step up costs more than the last
+        assert_eq!(calculate_upgrade_cost(&economy(), RARITY_COMMON), 100);
+        assert_eq!(calculate_upgrade_cost(&economy(), RARITY_LEGENDARY), 500);
+    }
+
+    #[test]
+    fn test_calculate_speed_gain() {
+        // Slower or barely faster combatants gain nothing
+        assert_eq!(calculate_speed_gain(&combat(), 10, 20), 0);
+        assert_eq!(calculate_speed_gain(&combat(), 11, 10), 0); // 10% faster
+
+        // 50% faster fills half a gauge, twice as fast fills a whole one
+        assert_eq!(calculate_speed_gain(&combat(), 15, 10), 5000);
+        assert_eq!(calculate_speed_gain(&combat(), 20, 10), SPEED_GAUGE_FULL);
+        assert_eq!(calculate_speed_gain(&combat(), 100, 10), SPEED_GAUGE_FULL);
+        assert_eq!(calculate_speed_gain(&combat(), 10, 0), SPEED_GAUGE_FULL);
+    }
+
+    #[test]
+    fn test_config_overrides() {
+        let mut combat = combat();
+        combat.critical_multiplier = 3;
+        combat.base_hp_multiplier = 20;
+        combat.max_escape_chance = 5000;
+        assert_eq!(calculate_critical_damage(&combat, 50), 150);
+        assert_eq!(calculate_hp(&combat, 50), 1000);
+        assert_eq!(calculate_escape_chance(&combat, 200, 10), 5000);
+
+        let mut economy = economy();
+        economy.xp_per_level_base = 500;
+        assert_eq!(calculate_xp_required(&economy, 1), 1000);
+    }
+
+    #[test]
+    fn test_calculate_luck_modifier() {
+        assert_eq!(calculate_luck_modifier(&economy(), 0), 0);
+        assert_eq!(calculate_luck_modifier(&economy(), LUCK_HALF_POINT), MAX_LUCK_BONUS / 2);
+        assert!(calculate_luck_modifier(&economy(), u16::MAX) <= MAX_LUCK_BONUS);
+
+        // Diminishing returns: the second 50 points are worth less than the first 50
+        let first = calculate_luck_modifier(&economy(), 50);
+        let second = calculate_luck_modifier(&economy(), 100) - first;
+        assert!(second < first);
+    }
+
+    #[test]
+    fn test_calculate_soft_pity_bonus() {
+        assert_eq!(calculate_soft_pity_bonus(&economy(), 0), 0);
+        assert_eq!(calculate_soft_pity_bonus(&economy(), PITY_SOFT_START - 1), 0);
+        assert_eq!(calculate_soft_pity_bonus(&economy(), PITY_SOFT_START), PITY_SOFT_BONUS);
+        assert_eq!(calculate_soft_pity_bonus(&economy(), PITY_SOFT_START + 1), PITY_SOFT_BONUS * 2);
+        assert_eq!(calculate_soft_pity_bonus(&economy(), u16::MAX), 10000);
+    }
+}
@@ -1,7 +1,15 @@
 pub mod rng;
 pub mod math;
 pub mod validation;
+pub mod combat;
+pub mod randomness;
+pub mod items;
+pub mod vault;
 
 pub use rng::*;
 pub use math::*;
-pub use validation::*;
\ No newline at end of file
+pub use validation::*;
+pub use combat::*;
+pub use randomness::*;
+pub use items::*;
+pub use vault::*;
\ No newline at end of file
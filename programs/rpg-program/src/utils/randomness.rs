@@ -0,0 +1,485 @@
+//! Randomness providers shared by rolls and battles
+//! The SlotHashes sysvar is a u64 entry count followed by (slot, hash) pairs,
+//! newest slot first. Oracle requests are written by a separate fulfiller program and
+//! read through the OracleLayout the session was started with.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::slot_hashes;
+use solana_sha256_hasher::hashv;
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::state::OracleLayout;
+use crate::utils::validation::validate_randomness_delay;
+
+const SLOT_HASHES_HEADER_LEN: usize = 8;
+const SLOT_HASH_ENTRY_LEN: usize = 8 + 32; // slot + hash
+
+/// Fields of an oracle request account, read through an OracleLayout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OracleRequest {
+    pub requester: Pubkey, // Roll session or battle the randomness is for
+    pub request_slot: u64,
+    pub fulfilled: bool,
+    pub randomness: [u8; 32],
+}
+
+/// Oracle a session was started with, and the request account passed for it if any
+pub struct SessionOracle<'a, 'info> {
+    pub program: &'a Pubkey,
+    pub layout: &'a OracleLayout,
+    pub request: Option<&'a AccountInfo<'info>>,
+}
+
+impl SessionOracle<'_, '_> {
+    /// Read the passed request, which must belong to the session's oracle program
+    fn read_request(&self, requester: &Pubkey, commit_slot: u64) -> Result<Option<OracleRequest>> {
+        let Some(request) = self.request else {
+            return Ok(None);
+        };
+        require_keys_eq!(*request.owner, *self.program, RpgError::InvalidOracleRequest);
+
+        let data = request.try_borrow_data()?;
+        parse_oracle_request(&data, self.layout, requester, commit_slot).map(Some)
+    }
+}
+
+/// Reveal the seed for a session committed at `commit_slot` from its provider
+pub fn reveal_randomness(
+    provider: u8,
+    oracle: SessionOracle,
+    requester: &Pubkey,
+    commit_slot: u64,
+    current_slot: u64,
+    slot_hashes_account: &AccountInfo,
+) -> Result<[u8; 32]> {
+    match provider {
+        RANDOMNESS_PROVIDER_SLOT_HASHES => {
+            validate_randomness_delay(current_slot, commit_slot)?;
+            slot_hash_for(slot_hashes_account, commit_slot + MIN_RANDOMNESS_DELAY_SLOTS)
+        }
+        RANDOMNESS_PROVIDER_ORACLE => {
+            let request = oracle
+                .read_request(requester, commit_slot)?
+                .ok_or(RpgError::InvalidOracleRequest)?;
+            require!(request.fulfilled, RpgError::OracleRandomnessPending);
+            Ok(request.randomness)
+        }
+        _ => Err(RpgError::InvalidRandomnessProvider.into()),
+    }
+}
+
+/// Validate raw oracle request data was made for the session after its commit
+pub fn parse_oracle_request(
+    data: &[u8],
+    layout: &OracleLayout,
+    requester: &Pubkey,
+    commit_slot: u64,
+) -> Result<OracleRequest> {
+    require!(
+        data.get(..ORACLE_DISCRIMINATOR_LEN) == Some(&layout.discriminator[..]),
+        RpgError::InvalidOracleRequest
+    );
+    let request = OracleRequest {
+        requester: Pubkey::new_from_array(read_field(data, layout.requester_offset)?),
+        request_slot: u64::from_le_bytes(read_field(data, layout.request_slot_offset)?),
+        fulfilled: read_field::<1>(data, layout.fulfilled_offset)?[0] != 0,
+        randomness: read_field(data, layout.randomness_offset)?,
+    };
+
+    require_keys_eq!(request.requester, *requester, RpgError::InvalidOracleRequest);
+    // Requests made before the commit could have been fulfilled in advance
+    require!(request.request_slot >= commit_slot, RpgError::StaleOracleRequest);
+
+    Ok(request)
+}
+
+fn read_field<const N: usize>(data: &[u8], offset: u16) -> Result<[u8; N]> {
+    let start = offset as usize;
+    data.get(start..start + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| RpgError::InvalidOracleRequest.into())
+}
+
+/// Whether a pending session's randomness provably never existed, the only case its entry
+/// is refunded: a skipped reveal slot, or an oracle request that is still unfulfilled
+pub fn randomness_never_existed(
+    provider: u8,
+    oracle: SessionOracle,
+    requester: &Pubkey,
+    commit_slot: u64,
+    slot_hashes_account: &AccountInfo,
+) -> Result<bool> {
+    match provider {
+        RANDOMNESS_PROVIDER_SLOT_HASHES => {
+            require!(
+                slot_hashes_account.key == &slot_hashes::id(),
+                RpgError::InvalidSlotHashes
+            );
+            let data = slot_hashes_account.try_borrow_data()?;
+            reveal_slot_skipped(&data, commit_slot + MIN_RANDOMNESS_DELAY_SLOTS)
+        }
+        // Without the request there's no showing it was never fulfilled
+        RANDOMNESS_PROVIDER_ORACLE => Ok(oracle
+            .read_request(requester, commit_slot)?
+            .is_some_and(|request| !request.fulfilled)),
+        _ => Err(RpgError::InvalidRandomnessProvider.into()),
+    }
+}
+
+/// Whether `reveal_slot` was skipped by its leader, so its hash never existed.
+/// An expired hash did exist and may already have been seen
+pub fn reveal_slot_skipped(data: &[u8], reveal_slot: u64) -> Result<bool> {
+    match find_slot_hash(data, reveal_slot) {
+        Ok(_) => Ok(false),
+        Err(error) if error == RpgError::SlotNotFound.into() => Ok(true),
+        Err(error) if error == RpgError::SlotHashExpired.into() => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+/// Commitment to a player secret, submitted at roll or battle start
+pub fn hash_player_secret(secret: &[u8; 32]) -> [u8; 32] {
+    hashv(&[PLAYER_SECRET_DOMAIN, secret]).to_bytes()
+}
+
+/// Check a revealed secret against its commitment
+pub fn verify_player_secret(secret_hash: &[u8; 32], secret: &[u8; 32]) -> Result<()> {
+    require!(
+        hash_player_secret(secret) == *secret_hash,
+        RpgError::InvalidSecretReveal
+    );
+    Ok(())
+}
+
+/// Mix provider randomness with the player's secret so neither side controls the seed
+pub fn mix_player_secret(randomness: &[u8; 32], secret: &[u8; 32]) -> [u8; 32] {
+    hashv(&[randomness, secret]).to_bytes()
+}
+
+/// Check a slot-hash session may move its commit forward: it has recommits left and its
+/// reveal slot was skipped, so no hash for it ever existed
+pub fn validate_recommit(
+    provider: u8,
+    slot_hashes_account: &AccountInfo,
+    commit_slot: u64,
+    recommit_count: u8,
+) -> Result<()> {
+    // Oracle requests are keyed by session and can't be requested twice
+    require!(
+        provider == RANDOMNESS_PROVIDER_SLOT_HASHES,
+        RpgError::InvalidRandomnessProvider
+    );
+    require!(recommit_count < MAX_RECOMMITS, RpgError::RecommitLimitReached);
+    require!(
+        slot_hashes_account.key == &slot_hashes::id(),
+        RpgError::InvalidSlotHashes
+    );
+
+    let data = slot_hashes_account.try_borrow_data()?;
+    reveal_slot_recommittable(&data, commit_slot + MIN_RANDOMNESS_DELAY_SLOTS)
+}
+
+/// Succeeds only if `reveal_slot` was skipped by its leader. An expired hash did exist and
+/// may already have been seen, so recommitting it would be a free reroll
+pub fn reveal_slot_recommittable(data: &[u8], reveal_slot: u64) -> Result<()> {
+    match find_slot_hash(data, reveal_slot) {
+        Ok(_) => err!(RpgError::RevealSlotAvailable),
+        Err(error) if error == RpgError::SlotNotFound.into() => Ok(()),
+        Err(error) => Err(error),
+    }
+}
+
+/// Read the hash of `reveal_slot` from the SlotHashes sysvar account
+pub fn slot_hash_for(slot_hashes_account: &AccountInfo, reveal_slot: u64) -> Result<[u8; 32]> {
+    require!(
+        slot_hashes_account.key == &slot_hashes::id(),
+        RpgError::InvalidSlotHashes
+    );
+
+    let data = slot_hashes_account.try_borrow_data()?;
+    find_slot_hash(&data, reveal_slot)
+}
+
+/// Binary search the raw sysvar data for `reveal_slot`
+pub fn find_slot_hash(data: &[u8], reveal_slot: u64) -> Result<[u8; 32]> {
+    let header: [u8; 8] = data
+        .get(..SLOT_HASHES_HEADER_LEN)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(RpgError::InvalidSlotHashes)?;
+    let count = u64::from_le_bytes(header) as usize;
+
+    // Every declared entry must be present
+    let entries_end = count
+        .checked_mul(SLOT_HASH_ENTRY_LEN)
+        .and_then(|len| len.checked_add(SLOT_HASHES_HEADER_LEN))
+        .ok_or(RpgError::InvalidSlotHashes)?;
+    let entries = data
+        .get(SLOT_HASHES_HEADER_LEN..entries_end)
+        .ok_or(RpgError::InvalidSlotHashes)?;
+
+    if count == 0 {
+        return err!(RpgError::SlotHashInFuture);
+    }
+
+    let newest = entry_slot(entries, 0);
+    let oldest = entry_slot(entries, count - 1);
+    require!(reveal_slot <= newest, RpgError::SlotHashInFuture);
+    require!(reveal_slot >= oldest, RpgError::SlotHashExpired);
+
+    // Slots are sorted descending
+    let mut low = 0;
+    let mut high = count;
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let slot = entry_slot(entries, mid);
+        if slot == reveal_slot {
+            let start = mid * SLOT_HASH_ENTRY_LEN + 8;
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(&entries[start..start + 32]);
+            return Ok(hash);
+        } else if slot > reveal_slot {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    // Within range but skipped by the leader
+    err!(RpgError::SlotNotFound)
+}
+
+fn entry_slot(entries: &[u8], index: usize) -> u64 {
+    let start = index * SLOT_HASH_ENTRY_LEN;
+    let mut slot = [0u8; 8];
+    slot.copy_from_slice(&entries[start..start + 8]);
+    u64::from_le_bytes(slot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a sysvar buffer, hash bytes are the low byte of the slot
+    fn slot_hashes_data(slots: &[u64]) -> Vec<u8> {
+        let mut data = (slots.len() as u64).to_le_bytes().to_vec();
+        for slot in slots {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(&[*slot as u8; 32]);
+        }
+        data
+    }
+
+    #[test]
+    fn test_find_slot_hash() {
+        let slots: Vec<u64> = (100..=200).rev().collect();
+        let data = slot_hashes_data(&slots);
+
+        for slot in [100, 101, 150, 199, 200] {
+            assert_eq!(find_slot_hash(&data, slot).unwrap(), [slot as u8; 32]);
+        }
+    }
+
+    #[test]
+    fn test_expired_and_future_slots() {
+        let data = slot_hashes_data(&[120, 110, 100]);
+
+        assert_eq!(
+            find_slot_hash(&data, 99).unwrap_err(),
+            RpgError::SlotHashExpired.into()
+        );
+        assert_eq!(
+            find_slot_hash(&data, 121).unwrap_err(),
+            RpgError::SlotHashInFuture.into()
+        );
+        // Skipped slot inside the window
+        assert_eq!(
+            find_slot_hash(&data, 105).unwrap_err(),
+            RpgError::SlotNotFound.into()
+        );
+    }
+
+    #[test]
+    fn test_malformed_data() {
+        assert_eq!(
+            find_slot_hash(&[0u8; 4], 1).unwrap_err(),
+            RpgError::InvalidSlotHashes.into()
+        );
+
+        // Count claims more entries than the buffer holds
+        let mut data = slot_hashes_data(&[10, 9]);
+        data.truncate(data.len() - 1);
+        assert_eq!(
+            find_slot_hash(&data, 9).unwrap_err(),
+            RpgError::InvalidSlotHashes.into()
+        );
+
+        let mut data = slot_hashes_data(&[10]);
+        data[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(
+            find_slot_hash(&data, 10).unwrap_err(),
+            RpgError::InvalidSlotHashes.into()
+        );
+
+        assert_eq!(
+            find_slot_hash(&slot_hashes_data(&[]), 1).unwrap_err(),
+            RpgError::SlotHashInFuture.into()
+        );
+    }
+
+    #[test]
+    fn test_reveal_slot_recommittable() {
+        let data = slot_hashes_data(&[120, 110, 100]);
+
+        // Only a skipped reveal slot can be recommitted
+        assert!(reveal_slot_recommittable(&data, 105).is_ok());
+
+        // An expired hash existed, the player may have seen the outcome
+        assert_eq!(
+            reveal_slot_recommittable(&data, 99).unwrap_err(),
+            RpgError::SlotHashExpired.into()
+        );
+        // A readable hash means the result is already fixed
+        assert_eq!(
+            reveal_slot_recommittable(&data, 110).unwrap_err(),
+            RpgError::RevealSlotAvailable.into()
+        );
+        // Not revealed yet, nothing to recommit
+        assert_eq!(
+            reveal_slot_recommittable(&data, 121).unwrap_err(),
+            RpgError::SlotHashInFuture.into()
+        );
+    }
+
+    #[test]
+    fn test_reveal_slot_skipped() {
+        let data = slot_hashes_data(&[120, 110, 100]);
+
+        // Only a skipped slot never had a hash, so only it lets a session be refunded
+        assert!(reveal_slot_skipped(&data, 105).unwrap());
+
+        // An available or expired hash may already have shown the outcome
+        assert!(!reveal_slot_skipped(&data, 110).unwrap());
+        assert!(!reveal_slot_skipped(&data, 99).unwrap());
+
+        assert_eq!(
+            reveal_slot_skipped(&data, 121).unwrap_err(),
+            RpgError::SlotHashInFuture.into()
+        );
+    }
+
+    #[test]
+    fn test_player_secret_reveal() {
+        let secret = [3u8; 32];
+        let secret_hash = hash_player_secret(&secret);
+
+        assert!(verify_player_secret(&secret_hash, &secret).is_ok());
+        assert_eq!(
+            verify_player_secret(&secret_hash, &[4u8; 32]).unwrap_err(),
+            RpgError::InvalidSecretReveal.into()
+        );
+
+        // Changing either input changes the seed
+        let randomness = [5u8; 32];
+        let seed = mix_player_secret(&randomness, &secret);
+        assert_ne!(seed, randomness);
+        assert_ne!(seed, mix_player_secret(&[6u8; 32], &secret));
+        assert_ne!(seed, mix_player_secret(&randomness, &[4u8; 32]));
+    }
+
+    #[test]
+    fn test_oracle_request_flow() {
+        use mock_oracle::RandomnessRequest;
+
+        let layout = OracleLayout::default();
+        assert_eq!(RandomnessRequest::DISCRIMINATOR, &layout.discriminator[..]);
+
+        let requester = Pubkey::new_unique();
+        let commit_slot = 50;
+        let mut request = RandomnessRequest {
+            requester,
+            request_slot: commit_slot,
+            fulfilled: false,
+            randomness: [0u8; 32],
+            fulfilled_slot: 0,
+            bump: 255,
+            commit_slot,
+        };
+        let serialize = |request: &RandomnessRequest| {
+            let mut data = Vec::new();
+            request.try_serialize(&mut data).unwrap();
+            data
+        };
+        let parse = |data: &[u8], requester: &Pubkey, commit_slot: u64| {
+            parse_oracle_request(data, &layout, requester, commit_slot)
+        };
+
+        // Requested but not fulfilled yet, cancelling still refunds
+        let pending = parse(&serialize(&request), &requester, commit_slot).unwrap();
+        assert_eq!(pending.requester, requester);
+        assert_eq!(pending.request_slot, commit_slot);
+        assert!(!pending.fulfilled);
+
+        // The fulfiller writes the randomness back
+        request.fulfill([9u8; 32], commit_slot + 3).unwrap();
+        let data = serialize(&request);
+        let fulfilled = parse(&data, &requester, commit_slot).unwrap();
+        assert!(fulfilled.fulfilled);
+        assert_eq!(fulfilled.randomness, [9u8; 32]);
+
+        // Only the session that requested it, and only for requests after the commit
+        assert_eq!(
+            parse(&data, &Pubkey::new_unique(), commit_slot).unwrap_err(),
+            RpgError::InvalidOracleRequest.into()
+        );
+        assert_eq!(
+            parse(&data, &requester, commit_slot + 1).unwrap_err(),
+            RpgError::StaleOracleRequest.into()
+        );
+
+        // Other accounts are rejected
+        let mut wrong_type = data.clone();
+        wrong_type[0] ^= 1;
+        assert_eq!(
+            parse(&wrong_type, &requester, commit_slot).unwrap_err(),
+            RpgError::InvalidOracleRequest.into()
+        );
+        assert_eq!(
+            parse(&data[..20], &requester, commit_slot).unwrap_err(),
+            RpgError::InvalidOracleRequest.into()
+        );
+    }
+
+    #[test]
+    fn test_custom_oracle_layout() {
+        // An oracle keeping its randomness first and its flag as the last byte
+        let layout = OracleLayout {
+            discriminator: [1u8; ORACLE_DISCRIMINATOR_LEN],
+            randomness_offset: 8,
+            requester_offset: 40,
+            request_slot_offset: 72,
+            fulfilled_offset: 80,
+        };
+        let requester = Pubkey::new_unique();
+        let mut data = vec![1u8; ORACLE_DISCRIMINATOR_LEN];
+        data.extend_from_slice(&[7u8; 32]);
+        data.extend_from_slice(requester.as_ref());
+        data.extend_from_slice(&60u64.to_le_bytes());
+        data.push(1);
+
+        let request = parse_oracle_request(&data, &layout, &requester, 60).unwrap();
+        assert!(request.fulfilled);
+        assert_eq!(request.randomness, [7u8; 32]);
+        assert_eq!(request.request_slot, 60);
+
+        // Read through the mock's layout the same bytes are someone else's account
+        assert_eq!(
+            parse_oracle_request(&data, &OracleLayout::default(), &requester, 60).unwrap_err(),
+            RpgError::InvalidOracleRequest.into()
+        );
+        // Fields past the end of the account are rejected
+        assert_eq!(
+            parse_oracle_request(&data[..80], &layout, &requester, 60).unwrap_err(),
+            RpgError::InvalidOracleRequest.into()
+        );
+    }
+}
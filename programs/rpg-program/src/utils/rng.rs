@@ -1,97 +1,473 @@
-/// Random number generation utilities using slot hashes
-/// Based on ponzimon-program's secure RNG system
-
-/// Generate a pseudo-random u16 from seed and turn counter
-pub fn rng_u16(seed: &[u8; 32], turn: u8) -> u16 {
-    let mut result = [0u8; 2];
-    result[0] = seed[0] ^ seed[16] ^ turn;
-    result[1] = seed[1] ^ seed[17] ^ turn.wrapping_mul(3);
-    
-    u16::from_le_bytes(result)
+//! Random number generation utilities using slot hashes
+//! Every draw is SHA-256 over a domain tag, the revealed seed, the turn and a draw
+//! counter, so separate checks in the same turn are independent of each other.
+
+use solana_sha256_hasher::hashv;
+
+// Domain tags, one per kind of random check
+pub const RNG_DOMAIN_CRIT: &[u8] = b"crit";
+pub const RNG_DOMAIN_ESCAPE: &[u8] = b"escape";
+pub const RNG_DOMAIN_ROLL_RARITY: &[u8] = b"roll_rarity";
+pub const RNG_DOMAIN_PITY: &[u8] = b"pity";
+pub const RNG_DOMAIN_LOOT_PICK: &[u8] = b"loot_pick";
+pub const RNG_DOMAIN_LOOT_DROP: &[u8] = b"loot_drop";
+pub const RNG_DOMAIN_LOOT_RARITY: &[u8] = b"loot_rarity";
+pub const RNG_DOMAIN_LOOT_UPGRADE: &[u8] = b"loot_upgrade";
+
+/// Hash a single draw from the seed
+pub fn rng_hash(seed: &[u8; 32], domain: &[u8], turn: u8, draw: u32) -> [u8; 32] {
+    hashv(&[
+        &[domain.len() as u8],
+        domain,
+        seed,
+        &[turn],
+        &draw.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Generate a pseudo-random u16 for one draw
+pub fn rng_u16(seed: &[u8; 32], domain: &[u8], turn: u8, draw: u32) -> u16 {
+    let hash = rng_hash(seed, domain, turn, draw);
+    u16::from_le_bytes([hash[0], hash[1]])
+}
+
+/// Generate a pseudo-random u32 for one draw
+pub fn rng_u32(seed: &[u8; 32], domain: &[u8], turn: u8, draw: u32) -> u32 {
+    let hash = rng_hash(seed, domain, turn, draw);
+    u32::from_le_bytes([hash[0], hash[1], hash[2], hash[3]])
+}
+
+/// Generate a pseudo-random u64 for one draw
+pub fn rng_u64(seed: &[u8; 32], domain: &[u8], turn: u8, draw: u32) -> u64 {
+    let hash = rng_hash(seed, domain, turn, draw);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Stream of independent draws for one turn, each call advances the draw counter
+#[derive(Clone, Copy, Debug)]
+pub struct RngStream {
+    seed: [u8; 32],
+    turn: u8,
+    draws: u32,
+}
+
+impl RngStream {
+    pub fn new(seed: &[u8; 32], turn: u8) -> Self {
+        Self {
+            seed: *seed,
+            turn,
+            draws: 0,
+        }
+    }
+
+    pub fn next_u64(&mut self, domain: &[u8]) -> u64 {
+        let value = rng_u64(&self.seed, domain, self.turn, self.draws);
+        self.draws = self.draws.wrapping_add(1);
+        value
+    }
+
+    /// Random number in range [min, max]
+    pub fn range(&mut self, domain: &[u8], min: u32, max: u32) -> u32 {
+        if min >= max {
+            return min;
+        }
+
+        // 64 bits of entropy keep the modulo bias negligible
+        let range = (max - min) as u64 + 1;
+        min + (self.next_u64(domain) % range) as u32
+    }
+
+    /// Check if random event occurs (percentage out of 10000 for precision)
+    pub fn check(&mut self, domain: &[u8], threshold: u16) -> bool {
+        (self.next_u64(domain) % 10000) < threshold as u64
+    }
+
+    pub fn draws(&self) -> u32 {
+        self.draws
+    }
 }
 
-/// Generate a pseudo-random u32 from seed and turn counter
-pub fn rng_u32(seed: &[u8; 32], turn: u8) -> u32 {
-    let mut result = [0u8; 4];
-    result[0] = seed[0] ^ seed[8] ^ turn;
-    result[1] = seed[1] ^ seed[9] ^ turn.wrapping_mul(3);
-    result[2] = seed[2] ^ seed[10] ^ turn.wrapping_mul(5);
-    result[3] = seed[3] ^ seed[11] ^ turn.wrapping_mul(7);
-    
-    u32::from_le_bytes(result)
+/// Determine roll reward rarity from odds table weights (out of ODDS_TOTAL_WEIGHT).
+/// The luck modifier (out of 10000) moves the draw that far towards the top of the range.
+pub fn determine_roll_rarity(
+    seed: &[u8; 32],
+    draw: u32,
+    weights: &[u16; crate::constants::RARITY_COUNT],
+    luck_modifier: u16,
+) -> u8 {
+    let total = crate::constants::ODDS_TOTAL_WEIGHT as u32;
+    let random = rng_u32(seed, RNG_DOMAIN_ROLL_RARITY, 0, draw);
+    let mut roll = (random as u64 * total as u64 / (u32::MAX as u64 + 1)) as u32;
+    roll += (total - 1 - roll) * std::cmp::min(luck_modifier, 10000) as u32 / 10000;
+
+    // Walk the cumulative weights
+    let mut cumulative = 0u32;
+    for (rarity, &weight) in weights.iter().enumerate() {
+        cumulative += weight as u32;
+        if roll < cumulative {
+            return rarity as u8;
+        }
+    }
+    crate::constants::RARITY_MYTHIC
 }
 
-/// Generate random number in range [min, max]
-pub fn rng_range(seed: &[u8; 32], turn: u8, min: u32, max: u32) -> u32 {
-    if min >= max {
-        return min;
+/// Determine roll rarity for a hero with `pity` consecutive misses below the pity floor
+pub fn determine_roll_rarity_with_pity(
+    seed: &[u8; 32],
+    draw: u32,
+    weights: &[u16; crate::constants::RARITY_COUNT],
+    pity: u16,
+    luck: u16,
+    config: &crate::state::EconomyConfig,
+) -> u8 {
+    let luck_modifier = crate::utils::math::calculate_luck_modifier(config, luck);
+    let rarity = determine_roll_rarity(seed, draw, weights, luck_modifier);
+    if rarity >= crate::constants::PITY_RARITY_FLOOR {
+        return rarity;
+    }
+
+    // Hard pity guarantees the floor, soft pity gives a growing chance to reach it
+    if pity >= config.pity_hard_limit {
+        return crate::constants::PITY_RARITY_FLOOR;
+    }
+    let soft_bonus = crate::utils::math::calculate_soft_pity_bonus(config, pity);
+    if rng_u64(seed, RNG_DOMAIN_PITY, 0, draw) % 10000 < soft_bonus as u64 {
+        crate::constants::PITY_RARITY_FLOOR
+    } else {
+        rarity
     }
-    
-    let range = max - min + 1;
-    let random = rng_u32(seed, turn);
-    min + (random % range)
 }
 
-/// Check if random event occurs (percentage out of 10000 for precision)
-pub fn rng_check(seed: &[u8; 32], turn: u8, threshold: u16) -> bool {
-    let roll = rng_u16(seed, turn) % 10000;
-    roll < threshold
+/// Pity counter after a roll, a hit on the floor resets it
+pub fn next_roll_pity(pity: u16, rarity: u8) -> u16 {
+    if rarity >= crate::constants::PITY_RARITY_FLOOR {
+        0
+    } else {
+        pity.saturating_add(1)
+    }
 }
 
-/// Generate random percentage (0-100)
-pub fn rng_percentage(seed: &[u8; 32], turn: u8) -> u8 {
-    (rng_u16(seed, turn) % 101) as u8
+/// Draw every roll of a session from one seed, advancing `pity` after each roll.
+/// A batch's guarantee is applied before its last roll counts towards pity.
+pub fn draw_roll_rarities(
+    seed: &[u8; 32],
+    rarities: &mut [u8],
+    weights: &[u16; crate::constants::RARITY_COUNT],
+    pity: &mut u16,
+    luck: u16,
+    config: &crate::state::EconomyConfig,
+) {
+    let roll_count = rarities.len();
+    for draw in 0..roll_count {
+        rarities[draw] = determine_roll_rarity_with_pity(seed, draw as u32, weights, *pity, luck, config);
+        if roll_count > 1 && draw == roll_count - 1 {
+            apply_batch_guarantee(rarities);
+        }
+        *pity = next_roll_pity(*pity, rarities[draw]);
+    }
 }
 
-/// Determine roll reward rarity based on random value
-pub fn determine_roll_rarity(seed: &[u8; 32], turn: u8) -> u8 {
-    let random_percent = (rng_u32(seed, turn) as u64 * 1000 / (u32::MAX as u64 + 1)) as u32;
-    
-    match random_percent {
-        0..=499 => crate::constants::RARITY_COMMON,        // 50.0%
-        500..=749 => crate::constants::RARITY_UNCOMMON,    // 25.0%
-        750..=899 => crate::constants::RARITY_RARE,        // 15.0%
-        900..=959 => crate::constants::RARITY_EPIC,        // 6.0%
-        960..=989 => crate::constants::RARITY_LEGENDARY,   // 3.0%
-        _ => crate::constants::RARITY_MYTHIC,              // 1.0%
+/// Raise the last roll of a batch when no roll reached the guaranteed rarity
+pub fn apply_batch_guarantee(rarities: &mut [u8]) {
+    let guaranteed = crate::constants::ROLL_BATCH_GUARANTEED_RARITY;
+    if rarities.iter().all(|&rarity| rarity < guaranteed) {
+        if let Some(last) = rarities.last_mut() {
+            *last = guaranteed;
+        }
     }
 }
 
+/// Roll a single loot drop from weighted entries.
+/// Returns the dropped item type, rarity and element, or None if nothing dropped.
+pub fn roll_loot(
+    seed: &[u8; 32],
+    turn: u8,
+    entries: &[crate::state::LootEntry],
+    luck: u16,
+    config: &crate::state::EconomyConfig,
+) -> Option<(u8, u8, u8)> {
+    let total_weight: u32 = entries.iter().map(|entry| entry.weight as u32).sum();
+    if total_weight == 0 {
+        return None;
+    }
+
+    let mut rng = RngStream::new(seed, turn);
+
+    // Pick an entry by weight
+    let mut pick = rng.range(RNG_DOMAIN_LOOT_PICK, 0, total_weight - 1);
+    let entry = entries.iter().find(|entry| {
+        if pick < entry.weight as u32 {
+            true
+        } else {
+            pick -= entry.weight as u32;
+            false
+        }
+    })?;
+
+    // Check whether it drops, luck raises the chance
+    let luck_modifier = crate::utils::math::calculate_luck_modifier(config, luck);
+    let drop_chance = entry.drop_chance.saturating_add(luck_modifier);
+    if !rng.check(RNG_DOMAIN_LOOT_DROP, drop_chance) {
+        return None;
+    }
+
+    // Rarity within the entry's range, luck may upgrade it one tier
+    let mut rarity = rng.range(
+        RNG_DOMAIN_LOOT_RARITY,
+        entry.min_rarity as u32,
+        entry.max_rarity as u32,
+    ) as u8;
+    if rarity < entry.max_rarity && rng.check(RNG_DOMAIN_LOOT_UPGRADE, luck_modifier) {
+        rarity += 1;
+    }
+
+    Some((entry.item_type, rarity, entry.element))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // 50/25/15/6/3/1
+    const STANDARD_WEIGHTS: [u16; 6] = [5000, 2500, 1500, 600, 300, 100];
+
     #[test]
     fn test_rng_u16() {
         let seed = [1u8; 32];
-        let result1 = rng_u16(&seed, 0);
-        let result2 = rng_u16(&seed, 1);
+        let result1 = rng_u16(&seed, RNG_DOMAIN_CRIT, 0, 0);
+        let result2 = rng_u16(&seed, RNG_DOMAIN_CRIT, 1, 0);
         
         // Different turns should give different results
         assert_ne!(result1, result2);
     }
 
     #[test]
-    fn test_rng_range() {
+    fn test_domains_and_draws_are_independent() {
         let seed = [1u8; 32];
-        let result = rng_range(&seed, 0, 10, 20);
-        
-        assert!(result >= 10 && result <= 20);
+
+        // Same seed and turn, different domain or draw
+        let crit = rng_u32(&seed, RNG_DOMAIN_CRIT, 3, 0);
+        assert_ne!(crit, rng_u32(&seed, RNG_DOMAIN_ESCAPE, 3, 0));
+        assert_ne!(crit, rng_u32(&seed, RNG_DOMAIN_CRIT, 3, 1));
+
+        // A stream advances its draw counter on every call
+        let mut rng = RngStream::new(&seed, 3);
+        let first = rng.next_u64(RNG_DOMAIN_CRIT);
+        let second = rng.next_u64(RNG_DOMAIN_CRIT);
+        assert_ne!(first, second);
+        assert_eq!(rng.draws(), 2);
     }
 
     #[test]
-    fn test_rng_percentage() {
+    fn test_rng_range() {
         let seed = [1u8; 32];
-        let result = rng_percentage(&seed, 0);
-        
-        assert!(result <= 100);
+        let mut rng = RngStream::new(&seed, 0);
+
+        for _ in 0..100 {
+            let result = rng.range(RNG_DOMAIN_LOOT_PICK, 10, 20);
+            assert!((10..=20).contains(&result));
+        }
+        assert_eq!(rng.range(RNG_DOMAIN_LOOT_PICK, 5, 5), 5);
     }
 
     #[test]
     fn test_determine_roll_rarity() {
         let seed = [1u8; 32];
-        let rarity = determine_roll_rarity(&seed, 0);
+        let rarity = determine_roll_rarity(&seed, 0, &STANDARD_WEIGHTS, 0);
         
         assert!(rarity <= crate::constants::RARITY_MYTHIC);
+
+        // The odds table decides the outcome
+        let all_common = [10000, 0, 0, 0, 0, 0];
+        let all_mythic = [0, 0, 0, 0, 0, 10000];
+        for draw in 0..100 {
+            assert_eq!(determine_roll_rarity(&seed, draw, &all_common, 0), crate::constants::RARITY_COMMON);
+            assert_eq!(determine_roll_rarity(&seed, draw, &all_mythic, 0), crate::constants::RARITY_MYTHIC);
+        }
+    }
+
+    #[test]
+    fn test_luck_shifts_roll_rarity() {
+        use crate::constants::{MAX_LUCK_BONUS, RARITY_COMMON, RARITY_MYTHIC};
+
+        let count = |luck_modifier: u16, rarity: u8| {
+            (0..10_000u32)
+                .filter(|i| {
+                    let mut seed = [0u8; 32];
+                    seed[..4].copy_from_slice(&i.to_le_bytes());
+                    determine_roll_rarity(&seed, 0, &STANDARD_WEIGHTS, luck_modifier) == rarity
+                })
+                .count()
+        };
+
+        // Luck moves rolls out of Common and into the top tiers
+        assert!(count(MAX_LUCK_BONUS, RARITY_COMMON) < count(0, RARITY_COMMON));
+        assert!(count(MAX_LUCK_BONUS, RARITY_MYTHIC) > count(0, RARITY_MYTHIC));
+
+        // A roll never gets worse with more luck
+        for i in 0..1000u32 {
+            let mut seed = [0u8; 32];
+            seed[..4].copy_from_slice(&i.to_le_bytes());
+            assert!(determine_roll_rarity(&seed, 0, &STANDARD_WEIGHTS, MAX_LUCK_BONUS) >= determine_roll_rarity(&seed, 0, &STANDARD_WEIGHTS, 0));
+        }
+    }
+
+    #[test]
+    fn test_roll_rarity_pity() {
+        use crate::constants::{PITY_HARD_LIMIT, PITY_RARITY_FLOOR, PITY_SOFT_START};
+
+        let config = crate::state::EconomyConfig::default();
+        let floor_rate = |pity: u16| {
+            (0..10_000u32)
+                .filter(|i| {
+                    let mut seed = [0u8; 32];
+                    seed[..4].copy_from_slice(&i.to_le_bytes());
+                    determine_roll_rarity_with_pity(&seed, 0, &STANDARD_WEIGHTS, pity, 0, &config) >= PITY_RARITY_FLOOR
+                })
+                .count()
+        };
+
+        // Base odds of Epic or better are 10%
+        assert!(floor_rate(0).abs_diff(1000) <= 100);
+        assert_eq!(floor_rate(PITY_SOFT_START - 1), floor_rate(0));
+
+        // Soft pity raises the odds, hard pity guarantees the floor
+        assert!(floor_rate(PITY_SOFT_START + 4) > floor_rate(PITY_SOFT_START));
+        assert_eq!(floor_rate(PITY_HARD_LIMIT), 10_000);
+
+        // Rolls that already beat the floor are unchanged
+        for i in 0..1000u32 {
+            let mut seed = [0u8; 32];
+            seed[..4].copy_from_slice(&i.to_le_bytes());
+            let rarity = determine_roll_rarity(&seed, 0, &STANDARD_WEIGHTS, 0);
+            let with_pity = determine_roll_rarity_with_pity(&seed, 0, &STANDARD_WEIGHTS, PITY_HARD_LIMIT, 0, &config);
+            assert_eq!(with_pity, std::cmp::max(rarity, PITY_RARITY_FLOOR));
+        }
+    }
+
+    #[test]
+    fn test_apply_batch_guarantee() {
+        use crate::constants::*;
+
+        let mut misses = [RARITY_COMMON; ROLL_BATCH_SIZE];
+        apply_batch_guarantee(&mut misses);
+        assert_eq!(misses[ROLL_BATCH_SIZE - 1], ROLL_BATCH_GUARANTEED_RARITY);
+        assert_eq!(misses.iter().filter(|&&r| r == RARITY_COMMON).count(), ROLL_BATCH_SIZE - 1);
+
+        // A batch that already hit the guarantee is untouched
+        let mut hit = [RARITY_COMMON; ROLL_BATCH_SIZE];
+        hit[2] = RARITY_EPIC;
+        let before = hit;
+        apply_batch_guarantee(&mut hit);
+        assert_eq!(hit, before);
+
+        // Rolls from one seed are independent of each other
+        let seed = [9u8; 32];
+        let rarities: Vec<u8> = (0..100).map(|draw| determine_roll_rarity(&seed, draw, &STANDARD_WEIGHTS, 0)).collect();
+        assert!(rarities.iter().any(|&r| r != rarities[0]));
+    }
+
+    #[test]
+    fn test_batch_guarantee_counts_towards_pity() {
+        use crate::constants::*;
+
+        let config = crate::state::EconomyConfig::default();
+        let mut commons = [0u16; RARITY_COUNT];
+        commons[RARITY_COMMON as usize] = ODDS_TOTAL_WEIGHT;
+
+        // Every draw misses, the last one is raised before pity sees it
+        let mut rarities = [0u8; ROLL_BATCH_SIZE];
+        let mut pity = 0;
+        draw_roll_rarities(&[1u8; 32], &mut rarities, &commons, &mut pity, 0, &config);
+        assert_eq!(rarities[ROLL_BATCH_SIZE - 1], ROLL_BATCH_GUARANTEED_RARITY);
+        assert_eq!(pity, next_roll_pity(ROLL_BATCH_SIZE as u16 - 1, ROLL_BATCH_GUARANTEED_RARITY));
+
+        // A single roll gets no guarantee
+        let mut single = [0u8; 1];
+        let mut pity = 5;
+        draw_roll_rarities(&[1u8; 32], &mut single, &commons, &mut pity, 0, &config);
+        assert_eq!(single, [RARITY_COMMON]);
+        assert_eq!(pity, 6);
+
+        // Hitting the floor resets pity
+        assert_eq!(next_roll_pity(30, PITY_RARITY_FLOOR), 0);
+    }
+
+    #[test]
+    fn test_roll_rarity_distribution() {
+        // Configured odds in tenths of a percent: 50/25/15/6/3/1
+        let expected = [500u32, 250, 150, 60, 30, 10];
+        let samples = 100_000u32;
+        let mut counts = [0u32; 6];
+
+        for i in 0..samples {
+            let mut seed = [0u8; 32];
+            seed[..4].copy_from_slice(&i.to_le_bytes());
+            counts[determine_roll_rarity(&seed, 0, &STANDARD_WEIGHTS, 0) as usize] += 1;
+        }
+
+        // Each bucket is within 0.5 percentage points of its odds
+        for (count, odds) in counts.iter().zip(expected.iter()) {
+            let observed = count * 1000 / samples;
+            assert!(
+                observed.abs_diff(*odds) <= 5,
+                "observed {} vs expected {} (per mille)",
+                observed,
+                odds
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_distribution() {
+        // 10 precision = 10% crit chance
+        let samples = 100_000u32;
+        let mut hits = 0u32;
+
+        for i in 0..samples {
+            let mut seed = [0u8; 32];
+            seed[..4].copy_from_slice(&i.to_le_bytes());
+            let mut rng = RngStream::new(&seed, (i % 256) as u8);
+            if rng.check(RNG_DOMAIN_CRIT, 1000) {
+                hits += 1;
+            }
+        }
+
+        let observed = hits * 1000 / samples;
+        assert!(observed.abs_diff(100) <= 5, "observed {} per mille", observed);
+    }
+
+    #[test]
+    fn test_roll_loot() {
+        use crate::state::{EconomyConfig, LootEntry};
+
+        let config = EconomyConfig::default();
+        let entries = [LootEntry {
+            item_type: crate::constants::ITEM_TYPE_ARMOR,
+            min_rarity: crate::constants::RARITY_UNCOMMON,
+            max_rarity: crate::constants::RARITY_EPIC,
+            element: crate::constants::ELEMENT_WATER,
+            weight: 1,
+            drop_chance: 10000,
+        }];
+
+        // Guaranteed drop stays within the entry's rarity range
+        for turn in 0..=u8::MAX {
+            let seed = [turn; 32];
+            let (item_type, rarity, element) = roll_loot(&seed, turn, &entries, 50, &config).unwrap();
+            assert_eq!(item_type, crate::constants::ITEM_TYPE_ARMOR);
+            assert_eq!(element, crate::constants::ELEMENT_WATER);
+            assert!(rarity >= crate::constants::RARITY_UNCOMMON);
+            assert!(rarity <= crate::constants::RARITY_EPIC);
+        }
+
+        // No entries or zero drop chance never drops
+        let seed = [7u8; 32];
+        assert!(roll_loot(&seed, 0, &[], 50, &config).is_none());
+        let never = [LootEntry { drop_chance: 0, ..entries[0] }];
+        assert!(roll_loot(&seed, 0, &never, 0, &config).is_none());
     }
 }
\ No newline at end of file
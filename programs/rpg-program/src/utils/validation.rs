@@ -8,7 +8,14 @@ pub fn validate_hero_index(index: u8, max_count: u8) -> Result<()> {
     Ok(())
 }
 
+/// Validate hero class id
+pub fn validate_hero_class(hero_class: u8) -> Result<()> {
+    require!((hero_class as usize) < HERO_CLASS_COUNT, RpgError::InvalidHeroClass);
+    Ok(())
+}
+
 /// Validate attribute distribution for level up
+#[allow(clippy::too_many_arguments)]
 pub fn validate_attribute_distribution(
     strength: u16,
     dexterity: u16,
@@ -39,13 +46,33 @@ pub fn validate_battle_action(action: u8) -> Result<()> {
     Ok(())
 }
 
+/// Validate an element id
+pub fn validate_element(element: u8) -> Result<()> {
+    require!((element as usize) < ELEMENT_COUNT, RpgError::InvalidElement);
+    Ok(())
+}
+
+/// Validate an elemental profile (element and resistance bounds)
+pub fn validate_elemental_profile(profile: &crate::state::ElementalProfile) -> Result<()> {
+    validate_element(profile.element)?;
+    require!(
+        profile
+            .resistances
+            .iter()
+            .all(|r| (MIN_ELEMENT_RESISTANCE..=MAX_ELEMENT_RESISTANCE).contains(r)),
+        RpgError::InvalidElement
+    );
+    Ok(())
+}
+
 /// Validate enemy template data
 pub fn validate_enemy_template(
     level: u16,
     name: &[u8; 32],
     ai_flags: u8,
+    max_level: u16,
 ) -> Result<()> {
-    require!(level > 0 && level <= MAX_LEVEL, RpgError::InvalidEnemyTemplate);
+    require!(level > 0 && level <= max_level, RpgError::InvalidEnemyTemplate);
     
     // Ensure name is not empty
     let name_str = String::from_utf8_lossy(name);
@@ -83,12 +110,154 @@ pub fn validate_item_data(
     Ok(())
 }
 
+/// Validate loot table entries
+pub fn validate_loot_entries(entries: &[crate::state::LootEntry]) -> Result<()> {
+    require!(
+        !entries.is_empty() && entries.len() <= MAX_LOOT_ENTRIES,
+        RpgError::InvalidLootTable
+    );
+
+    for entry in entries {
+        require!(entry.item_type <= ITEM_TYPE_ACCESSORY, RpgError::WrongItemType);
+        validate_element(entry.element)?;
+        require!(
+            entry.min_rarity <= entry.max_rarity && entry.max_rarity <= RARITY_MYTHIC,
+            RpgError::InvalidItemRarity
+        );
+        require!(
+            entry.weight > 0 && entry.drop_chance <= 10000,
+            RpgError::InvalidLootTable
+        );
+    }
+
+    Ok(())
+}
+
+/// Validate game config tuning values
+pub fn validate_game_config(
+    combat: &crate::state::CombatConfig,
+    economy: &crate::state::EconomyConfig,
+) -> Result<()> {
+    require!(
+        combat.base_hp_multiplier > 0
+            && combat.base_damage_reduction > 0
+            && combat.critical_multiplier > 0,
+        RpgError::InvalidGameConfig
+    );
+    require!(
+        combat.min_escape_chance <= combat.max_escape_chance
+            && combat.max_escape_chance <= 10000
+            && combat.base_escape_chance <= 10000,
+        RpgError::InvalidGameConfig
+    );
+    require!(
+        combat.element_advantage_multiplier >= 10000
+            && combat.element_disadvantage_multiplier <= 10000,
+        RpgError::InvalidGameConfig
+    );
+    // Above a full gauge no dexterity surplus could ever earn an extra action
+    require!(
+        combat.min_speed_advantage <= SPEED_GAUGE_FULL,
+        RpgError::InvalidGameConfig
+    );
+
+    require!(
+        economy.xp_per_level_base > 0
+            && economy.attribute_points_per_level > 0
+            && economy.max_level > 0
+            && economy.max_heroes_per_player > 0,
+        RpgError::InvalidGameConfig
+    );
+    require!(
+        economy.luck_half_point > 0 && economy.max_luck_bonus <= 10000,
+        RpgError::InvalidGameConfig
+    );
+    require!(
+        economy.pity_hard_limit > 0 && economy.pity_soft_start <= economy.pity_hard_limit,
+        RpgError::InvalidGameConfig
+    );
+    require!(
+        economy.referral_bps <= REVENUE_SHARE_TOTAL,
+        RpgError::InvalidGameConfig
+    );
+
+    Ok(())
+}
+
+/// Validate odds table weights sum to exactly ODDS_TOTAL_WEIGHT
+pub fn validate_odds_table(weights: &[u16; RARITY_COUNT]) -> Result<()> {
+    let total: u32 = weights.iter().map(|&weight| weight as u32).sum();
+    require!(total == ODDS_TOTAL_WEIGHT as u32, RpgError::InvalidOddsTable);
+    Ok(())
+}
+
+/// Validate revenue bucket shares sum to REVENUE_SHARE_TOTAL
+pub fn validate_revenue_split(shares: &[u16; REVENUE_BUCKET_COUNT]) -> Result<()> {
+    let total: u32 = shares.iter().map(|&share| share as u32).sum();
+    require!(total == REVENUE_SHARE_TOTAL as u32, RpgError::InvalidRevenueSplit);
+    Ok(())
+}
+
+/// Validate a banner's time window and featured pool, the pool may be empty
+pub fn validate_banner(
+    start_time: i64,
+    end_time: i64,
+    featured_min_rarity: u8,
+    featured_pool: &[crate::state::LootEntry],
+) -> Result<()> {
+    require!(start_time < end_time, RpgError::InvalidBanner);
+    require!(featured_min_rarity <= RARITY_MYTHIC, RpgError::InvalidBanner);
+    if !featured_pool.is_empty() {
+        validate_loot_entries(featured_pool)?;
+    }
+    Ok(())
+}
+
+/// Validate the randomness provider, the oracle provider needs an oracle program and a
+/// request layout whose fields sit after the discriminator without overlapping
+pub fn validate_randomness_provider(
+    provider: u8,
+    oracle_program: &Pubkey,
+    oracle_layout: &crate::state::OracleLayout,
+) -> Result<()> {
+    match provider {
+        RANDOMNESS_PROVIDER_SLOT_HASHES => Ok(()),
+        RANDOMNESS_PROVIDER_ORACLE => {
+            require!(*oracle_program != Pubkey::default(), RpgError::InvalidRandomnessProvider);
+
+            let mut fields = [
+                (oracle_layout.requester_offset as usize, 32),
+                (oracle_layout.request_slot_offset as usize, 8),
+                (oracle_layout.fulfilled_offset as usize, 1),
+                (oracle_layout.randomness_offset as usize, 32),
+            ];
+            fields.sort_unstable();
+            let mut end = ORACLE_DISCRIMINATOR_LEN;
+            for (offset, len) in fields {
+                require!(offset >= end, RpgError::InvalidRandomnessProvider);
+                end = offset + len;
+            }
+            Ok(())
+        }
+        _ => Err(RpgError::InvalidRandomnessProvider.into()),
+    }
+}
+
 /// Validate sufficient funds for operation
 pub fn validate_sufficient_funds(available: u64, required: u64) -> Result<()> {
     require!(available >= required, RpgError::InsufficientFunds);
     Ok(())
 }
 
+/// Validate a payout leaves at least `reserve` lamports in the treasury
+pub fn validate_treasury_withdrawal(balance: u64, reserve: u64, amount: u64) -> Result<()> {
+    let remaining = balance
+        .checked_sub(amount)
+        .ok_or(RpgError::InsufficientTreasuryFunds)?;
+    require!(remaining >= reserve, RpgError::InsufficientTreasuryFunds);
+    Ok(())
+}
+
 /// Validate randomness delay has passed
 pub fn validate_randomness_delay(current_slot: u64, commit_slot: u64) -> Result<()> {
     require!(
@@ -118,6 +287,13 @@ mod tests {
         assert!(validate_hero_index(5, 5).is_err());
     }
 
+    #[test]
+    fn test_validate_hero_class() {
+        assert!(validate_hero_class(HERO_CLASS_WARRIOR).is_ok());
+        assert!(validate_hero_class(HERO_CLASS_CLERIC).is_ok());
+        assert!(validate_hero_class(HERO_CLASS_COUNT as u8).is_err());
+    }
+
     #[test]
     fn test_validate_attribute_distribution() {
         // Valid distribution
@@ -137,10 +313,157 @@ mod tests {
         assert!(validate_battle_action(99).is_err());
     }
 
+    #[test]
+    fn test_validate_game_config() {
+        use crate::state::{CombatConfig, EconomyConfig};
+
+        let combat = CombatConfig::default();
+        let economy = EconomyConfig::default();
+        assert!(validate_game_config(&combat, &economy).is_ok());
+
+        // Division by zero and inverted bounds are rejected
+        assert!(validate_game_config(&CombatConfig { base_damage_reduction: 0, ..combat }, &economy).is_err());
+        assert!(validate_game_config(&CombatConfig { min_escape_chance: 9000, ..combat }, &economy).is_err());
+        assert!(validate_game_config(&CombatConfig { element_advantage_multiplier: 5000, ..combat }, &economy).is_err());
+        assert!(validate_game_config(&CombatConfig { min_speed_advantage: SPEED_GAUGE_FULL, ..combat }, &economy).is_ok());
+        assert!(validate_game_config(&CombatConfig { min_speed_advantage: SPEED_GAUGE_FULL + 1, ..combat }, &economy).is_err());
+
+        // Progression must stay possible
+        assert!(validate_game_config(&combat, &EconomyConfig { xp_per_level_base: 0, ..economy }).is_err());
+        assert!(validate_game_config(&combat, &EconomyConfig { max_heroes_per_player: 0, ..economy }).is_err());
+        assert!(validate_game_config(&combat, &EconomyConfig { max_level: 0, ..economy }).is_err());
+        assert!(validate_game_config(&combat, &EconomyConfig { max_luck_bonus: 10001, ..economy }).is_err());
+        assert!(validate_game_config(&combat, &EconomyConfig { luck_half_point: 0, ..economy }).is_err());
+        assert!(validate_game_config(&combat, &EconomyConfig { pity_soft_start: 61, ..economy }).is_err());
+        assert!(validate_game_config(&combat, &EconomyConfig { referral_bps: 10001, ..economy }).is_err());
+    }
+
+    #[test]
+    fn test_validate_odds_table() {
+        assert!(validate_odds_table(&[5000, 2500, 1500, 600, 300, 100]).is_ok());
+        assert!(validate_odds_table(&[10000, 0, 0, 0, 0, 0]).is_ok());
+        assert!(validate_odds_table(&[5000, 2500, 1500, 600, 300, 99]).is_err());
+        assert!(validate_odds_table(&[u16::MAX, u16::MAX, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_validate_revenue_split() {
+        assert!(validate_revenue_split(&[5000, 3000, 1500, 500]).is_ok());
+        assert!(validate_revenue_split(&[0, 0, 0, 10000]).is_ok());
+        assert!(validate_revenue_split(&[5000, 3000, 1500, 499]).is_err());
+        assert!(validate_revenue_split(&[u16::MAX, u16::MAX, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_validate_banner() {
+        use crate::state::LootEntry;
+
+        let featured = [LootEntry {
+            item_type: ITEM_TYPE_WEAPON,
+            min_rarity: RARITY_EPIC,
+            max_rarity: RARITY_MYTHIC,
+            element: ELEMENT_FIRE,
+            weight: 1,
+            drop_chance: 10000,
+        }];
+        assert!(validate_banner(100, 200, RARITY_EPIC, &featured).is_ok());
+        assert!(validate_banner(100, 200, RARITY_EPIC, &[]).is_ok());
+
+        // Empty or inverted windows and bad pools are rejected
+        assert!(validate_banner(200, 200, RARITY_EPIC, &featured).is_err());
+        assert!(validate_banner(300, 200, RARITY_EPIC, &featured).is_err());
+        assert!(validate_banner(100, 200, RARITY_MYTHIC + 1, &featured).is_err());
+        let bad = [LootEntry { weight: 0, ..featured[0] }];
+        assert!(validate_banner(100, 200, RARITY_EPIC, &bad).is_err());
+    }
+
+    #[test]
+    fn test_validate_randomness_provider() {
+        use crate::state::OracleLayout;
+
+        let oracle_program = Pubkey::new_unique();
+        let layout = OracleLayout::default();
+
+        assert!(validate_randomness_provider(RANDOMNESS_PROVIDER_SLOT_HASHES, &Pubkey::default(), &layout).is_ok());
+        assert!(validate_randomness_provider(RANDOMNESS_PROVIDER_ORACLE, &oracle_program, &layout).is_ok());
+        assert!(validate_randomness_provider(RANDOMNESS_PROVIDER_ORACLE, &Pubkey::default(), &layout).is_err());
+        assert!(validate_randomness_provider(2, &oracle_program, &layout).is_err());
+
+        // Fields may come in any order but can't overlap each other or the discriminator
+        let reordered = OracleLayout {
+            randomness_offset: 8,
+            requester_offset: 40,
+            fulfilled_offset: 72,
+            request_slot_offset: 80,
+            ..layout
+        };
+        assert!(validate_randomness_provider(RANDOMNESS_PROVIDER_ORACLE, &oracle_program, &reordered).is_ok());
+        let overlapping = OracleLayout { fulfilled_offset: 47, ..layout };
+        assert!(validate_randomness_provider(RANDOMNESS_PROVIDER_ORACLE, &oracle_program, &overlapping).is_err());
+        let in_discriminator = OracleLayout { requester_offset: 7, ..layout };
+        assert!(validate_randomness_provider(RANDOMNESS_PROVIDER_ORACLE, &oracle_program, &in_discriminator).is_err());
+    }
+
     #[test]
     fn test_validate_sufficient_funds() {
         assert!(validate_sufficient_funds(1000, 500).is_ok());
         assert!(validate_sufficient_funds(500, 1000).is_err());
         assert!(validate_sufficient_funds(1000, 1000).is_ok());
     }
+
+    #[test]
+    fn test_validate_treasury_withdrawal() {
+        assert!(validate_treasury_withdrawal(1000, 100, 900).is_ok());
+        assert_eq!(
+            validate_treasury_withdrawal(1000, 100, 901).unwrap_err(),
+            RpgError::InsufficientTreasuryFunds.into()
+        );
+        assert_eq!(
+            validate_treasury_withdrawal(1000, 0, 1001).unwrap_err(),
+            RpgError::InsufficientTreasuryFunds.into()
+        );
+    }
+
+    #[test]
+    fn test_validate_elemental_profile() {
+        use crate::state::ElementalProfile;
+
+        let profile = ElementalProfile {
+            element: ELEMENT_DARK,
+            resistances: [0, -5000, 0, 0, 0, 0, MAX_ELEMENT_RESISTANCE],
+        };
+        assert!(validate_elemental_profile(&profile).is_ok());
+
+        assert!(validate_elemental_profile(&ElementalProfile { element: 7, ..profile }).is_err());
+        let mut too_resistant = profile;
+        too_resistant.resistances[ELEMENT_FIRE as usize] = MAX_ELEMENT_RESISTANCE + 1;
+        assert!(validate_elemental_profile(&too_resistant).is_err());
+    }
+
+    #[test]
+    fn test_validate_loot_entries() {
+        use crate::state::LootEntry;
+
+        let entry = LootEntry {
+            item_type: ITEM_TYPE_WEAPON,
+            min_rarity: RARITY_COMMON,
+            max_rarity: RARITY_RARE,
+            element: ELEMENT_FIRE,
+            weight: 10,
+            drop_chance: 2500,
+        };
+        assert!(validate_loot_entries(&[entry]).is_ok());
+
+        // Empty or oversized tables
+        assert!(validate_loot_entries(&[]).is_err());
+        assert!(validate_loot_entries(&[entry; MAX_LOOT_ENTRIES + 1]).is_err());
+
+        // Bad entries
+        assert!(validate_loot_entries(&[LootEntry { item_type: 9, ..entry }]).is_err());
+        assert!(validate_loot_entries(&[LootEntry { min_rarity: RARITY_EPIC, ..entry }]).is_err());
+        assert!(validate_loot_entries(&[LootEntry { max_rarity: 6, ..entry }]).is_err());
+        assert!(validate_loot_entries(&[LootEntry { weight: 0, ..entry }]).is_err());
+        assert!(validate_loot_entries(&[LootEntry { element: 7, ..entry }]).is_err());
+        assert!(validate_loot_entries(&[LootEntry { drop_chance: 10001, ..entry }]).is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,103 @@
+//! Transfers in and out of the system-owned treasury vault, signed with its PDA seeds.
+//! Token payments go to per-mint treasury token accounts whose authority is the vault.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Token, TokenAccount};
+use crate::constants::*;
+use crate::errors::RpgError;
+use crate::utils::validation::validate_treasury_withdrawal;
+
+/// Send `amount` lamports from the vault, leaving it rent exempt plus `reserve`
+pub fn transfer_from_vault<'info>(
+    vault: &AccountInfo<'info>,
+    recipient: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    vault_bump: u8,
+    reserve: u64,
+    amount: u64,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(0);
+    validate_treasury_withdrawal(vault.lamports(), rent.saturating_add(reserve), amount)?;
+
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Transfer {
+                from: vault.clone(),
+                to: recipient.clone(),
+            },
+            &[&[VAULT_SEED, &[vault_bump]]],
+        ),
+        amount,
+    )
+}
+
+/// Optional accounts passed to pay or refund in an SPL token instead of SOL
+pub struct TokenPaymentAccounts<'a, 'info> {
+    pub player_token_account: &'a Account<'info, TokenAccount>,
+    pub treasury_token_account: &'a Account<'info, TokenAccount>,
+    pub token_program: &'a Program<'info, Token>,
+}
+
+impl<'a, 'info> TokenPaymentAccounts<'a, 'info> {
+    /// Some when every token account is passed, None when none are (SOL payment)
+    pub fn from_optional(
+        player_token_account: &'a Option<Account<'info, TokenAccount>>,
+        treasury_token_account: &'a Option<Account<'info, TokenAccount>>,
+        token_program: &'a Option<Program<'info, Token>>,
+    ) -> Result<Option<Self>> {
+        match (player_token_account, treasury_token_account, token_program) {
+            (Some(player_token_account), Some(treasury_token_account), Some(token_program)) => {
+                Ok(Some(Self {
+                    player_token_account,
+                    treasury_token_account,
+                    token_program,
+                }))
+            }
+            (None, None, _) => Ok(None),
+            _ => err!(RpgError::InvalidPaymentAccount),
+        }
+    }
+
+    /// Move `amount` from the player's token account into the treasury token account
+    pub fn collect(&self, player_authority: &Signer<'info>, mint: &Pubkey, amount: u64) -> Result<()> {
+        self.validate_mint(mint)?;
+
+        token::transfer(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                token::Transfer {
+                    from: self.player_token_account.to_account_info(),
+                    to: self.treasury_token_account.to_account_info(),
+                    authority: player_authority.to_account_info(),
+                },
+            ),
+            amount,
+        )
+    }
+
+    /// Move `amount` back to the player's token account, signed by the vault
+    pub fn refund(&self, vault: &AccountInfo<'info>, vault_bump: u8, mint: &Pubkey, amount: u64) -> Result<()> {
+        self.validate_mint(mint)?;
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                token::Transfer {
+                    from: self.treasury_token_account.to_account_info(),
+                    to: self.player_token_account.to_account_info(),
+                    authority: vault.clone(),
+                },
+                &[&[VAULT_SEED, &[vault_bump]]],
+            ),
+            amount,
+        )
+    }
+
+    fn validate_mint(&self, mint: &Pubkey) -> Result<()> {
+        require_keys_eq!(self.player_token_account.mint, *mint, RpgError::InvalidPaymentMint);
+        require_keys_eq!(self.treasury_token_account.mint, *mint, RpgError::InvalidPaymentMint);
+        Ok(())
+    }
+}
@@ -1,16 +1,667 @@
 import * as anchor from "@coral-xyz/anchor";
-import { Program } from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import { createHash, randomBytes } from "crypto";
+import { expect } from "chai";
 import { RpgProgram } from "../target/types/rpg_program";
+import { MockOracle } from "../target/types/mock_oracle";
+
+const { PublicKey, Keypair, LAMPORTS_PER_SOL, SYSVAR_SLOT_HASHES_PUBKEY } = anchor.web3;
+
+const ACTION_ATTACK = 0;
+const BATTLE_STATE_ACTIVE = 0;
+const MIN_RANDOMNESS_DELAY_SLOTS = 2;
+const CANCEL_TIMEOUT_SLOTS = 24;
+const RANDOMNESS_PROVIDER_SLOT_HASHES = 0;
+const RANDOMNESS_PROVIDER_ORACLE = 1;
 
 describe("rpg-program", () => {
   // Configure the client to use the local cluster.
-  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
 
   const program = anchor.workspace.rpgProgram as Program<RpgProgram>;
+  const oracleProgram = anchor.workspace.mockOracle as Program<MockOracle>;
+  const connection = provider.connection;
+  const admin = provider.wallet;
+
+  const pda = (...seeds: (Buffer | Uint8Array)[]) =>
+    PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+  const u8 = (value: number) => Buffer.from([value]);
+  const u32 = (value: number) => new BN(value).toArrayLike(Buffer, "le", 4);
+  const u64 = (value: number) => new BN(value).toArrayLike(Buffer, "le", 8);
+
+  const treasury = pda(Buffer.from("treasury"));
+  const vault = pda(Buffer.from("vault"));
+  const gameConfig = pda(Buffer.from("game_config"));
+
+  // Commitment to a player secret, matches utils::randomness::hash_player_secret
+  const hashSecret = (secret: Buffer) =>
+    Array.from(createHash("sha256").update("player_secret").update(secret).digest());
+
+  const waitForSlot = async (slot: number) => {
+    while ((await connection.getSlot("confirmed")) < slot) {
+      await new Promise((resolve) => setTimeout(resolve, 200));
+    }
+  };
+
+  const airdrop = async (to: anchor.web3.PublicKey, sol: number) => {
+    const signature = await connection.requestAirdrop(to, sol * LAMPORTS_PER_SOL);
+    await connection.confirmTransaction(signature, "confirmed");
+  };
+
+  const expectError = async (promise: Promise<unknown>, code: string) => {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (error) {
+      expect((error as anchor.AnchorError).error?.errorCode?.code).to.equal(code);
+    }
+  };
+
+  // A player with one hero, paying from its own wallet
+  const createPlayerWithHero = async (referrer: anchor.web3.PublicKey | null = null) => {
+    const authority = Keypair.generate();
+    await airdrop(authority.publicKey, 10);
+
+    const player = pda(Buffer.from("player"), authority.publicKey.toBuffer());
+    const hero = pda(Buffer.from("hero"), player.toBuffer(), u8(0));
+
+    await program.methods
+      .playerInitialize()
+      .accountsPartial({ authority: authority.publicKey, player, referrer })
+      .signers([authority])
+      .rpc();
+    await program.methods
+      .buyHero(0, 0)
+      .accountsPartial({
+        playerAuthority: authority.publicKey,
+        player,
+        hero,
+        gameConfig,
+        treasury,
+        vault,
+        playerTokenAccount: null,
+        treasuryTokenAccount: null,
+        tokenProgram: null,
+        referrer,
+      })
+      .signers([authority])
+      .rpc();
+
+    return { authority, player, hero, referrer };
+  };
+
+  // Enemy with one point in every attribute, beaten in a few attacks
+  const createWeakEnemy = async (id: number) => {
+    const enemyTemplate = pda(Buffer.from("enemy_template"), u32(id));
+    const name = Buffer.alloc(32);
+    name.write("Training Dummy");
+
+    await program.methods
+      .createEnemyTemplate(id, Array.from(name), 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 10, 0, [
+        0, 0, 0, 0, 0, 0, 0,
+      ])
+      .accountsPartial({ authority: admin.publicKey, enemyTemplate, gameConfig })
+      .rpc();
+
+    return enemyTemplate;
+  };
+
+  // Pay a battle's entry fee and commit to a secret, the fee stays in escrow until the first turn
+  const startBattle = async (
+    { authority, player, hero }: Awaited<ReturnType<typeof createPlayerWithHero>>,
+    enemyTemplate: anchor.web3.PublicKey,
+    nonce: number,
+    equippedWeapon: anchor.web3.PublicKey | null = null
+  ) => {
+    const battle = pda(Buffer.from("battle"), hero.toBuffer(), u64(nonce));
+    const secret = randomBytes(32);
+
+    await program.methods
+      .battleStart(new BN(nonce), hashSecret(secret), false)
+      .accountsPartial({
+        playerAuthority: authority.publicKey,
+        player,
+        hero,
+        enemyTemplate,
+        battle,
+        gameConfig,
+        treasury,
+        vault,
+        playerTokenAccount: null,
+        treasuryTokenAccount: null,
+        tokenProgram: null,
+        equippedWeapon,
+        equippedArmor: null,
+        equippedAccessory: null,
+      })
+      .signers([authority])
+      .rpc();
+
+    return { battle, secret };
+  };
+
+  // Start a battle and attack until it is over
+  const fightBattle = async (
+    owner: Awaited<ReturnType<typeof createPlayerWithHero>>,
+    enemyTemplate: anchor.web3.PublicKey,
+    nonce: number,
+    equippedWeapon: anchor.web3.PublicKey | null = null
+  ) => {
+    const { authority, player, hero, referrer } = owner;
+    const { battle, secret } = await startBattle(owner, enemyTemplate, nonce, equippedWeapon);
+
+    const { commitSlot } = await program.account.battle.fetch(battle);
+    await waitForSlot(commitSlot.toNumber() + MIN_RANDOMNESS_DELAY_SLOTS + 1);
+
+    let revealed = false;
+    while ((await program.account.battle.fetch(battle)).state === BATTLE_STATE_ACTIVE) {
+      await program.methods
+        .battleTurn(ACTION_ATTACK, revealed ? null : Array.from(secret))
+        .accountsPartial({
+          playerAuthority: authority.publicKey,
+          player,
+          hero,
+          enemyTemplate,
+          battle,
+          treasury,
+          slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+          oracleRequest: null,
+          referrer,
+        })
+        .signers([authority])
+        .rpc();
+      revealed = true;
+    }
+
+    return battle;
+  };
+
+  // Claim a finished battle's rewards, passing the hero's equipped items to wear
+  const settleBattle = (
+    { authority, player, hero }: Awaited<ReturnType<typeof createPlayerWithHero>>,
+    enemyTemplate: anchor.web3.PublicKey,
+    battle: anchor.web3.PublicKey,
+    equippedWeapon: anchor.web3.PublicKey | null = null
+  ) =>
+    program.methods
+      .battleSettle()
+      .accountsPartial({
+        playerAuthority: authority.publicKey,
+        player,
+        hero,
+        enemyTemplate,
+        battle,
+        gameConfig,
+        lootTable: null,
+        lootItem: null,
+        equippedWeapon,
+        equippedArmor: null,
+        equippedAccessory: null,
+      })
+      .signers([authority])
+      .rpc();
+
+  // Cancel a battle that never got its first turn, the oracle request is only read for oracle battles
+  const cancelBattle = (
+    { authority, player, hero }: Awaited<ReturnType<typeof createPlayerWithHero>>,
+    battle: anchor.web3.PublicKey,
+    oracleRequest: anchor.web3.PublicKey | null = null
+  ) =>
+    program.methods
+      .cancelPendingBattle()
+      .accountsPartial({
+        playerAuthority: authority.publicKey,
+        player,
+        hero,
+        battle,
+        treasury,
+        vault,
+        playerTokenAccount: null,
+        treasuryTokenAccount: null,
+        tokenProgram: null,
+        slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+        oracleRequest,
+        referrer: null,
+      })
+      .signers([authority])
+      .rpc();
+
+  before(async () => {
+    await program.methods
+      .initializeTreasury()
+      .accountsPartial({ authority: admin.publicKey, treasury, vault })
+      .rpc();
+    await program.methods
+      .initializeGameConfig()
+      .accountsPartial({ authority: admin.publicKey, treasury, gameConfig })
+      .rpc();
+  });
 
   it("Is initialized!", async () => {
-    // Add your test here.
-    const tx = await program.methods.initialize().rpc();
-    console.log("Your transaction signature", tx);
+    const treasuryAccount = await program.account.treasury.fetch(treasury);
+    expect(treasuryAccount.authority.toBase58()).to.equal(admin.publicKey.toBase58());
+
+    const config = await program.account.gameConfig.fetch(gameConfig);
+    expect(config.authority.toBase58()).to.equal(admin.publicKey.toBase58());
+  });
+
+  describe("battle settlement", () => {
+    it("settles a battle once and refunds its rent to the hero owner on close", async () => {
+      const owner = await createPlayerWithHero();
+      const enemyTemplate = await createWeakEnemy(1);
+      const battle = await fightBattle(owner, enemyTemplate, 0);
+
+      const settle = () => settleBattle(owner, enemyTemplate, battle);
+
+      await settle();
+      const settled = await program.account.battle.fetch(battle);
+      expect(settled.settled).to.equal(true);
+
+      // Rewards can't be claimed twice
+      await expectError(settle(), "BattleAlreadySettled");
+
+      // The admin pays the fee, so the owner's balance grows by exactly the battle's rent
+      const rent = (await connection.getAccountInfo(battle)).lamports;
+      const ownerBalance = await connection.getBalance(owner.authority.publicKey);
+
+      await program.methods
+        .closeBattle()
+        .accountsPartial({
+          caller: owner.authority.publicKey,
+          playerAuthority: owner.authority.publicKey,
+          hero: owner.hero,
+          battle,
+        })
+        .signers([owner.authority])
+        .rpc();
+
+      expect(await connection.getAccountInfo(battle)).to.equal(null);
+      expect(await connection.getBalance(owner.authority.publicKey)).to.equal(ownerBalance + rent);
+    });
+
+    it("rejects closing a battle with a rent receiver other than the hero owner", async () => {
+      const owner = await createPlayerWithHero();
+      const enemyTemplate = await createWeakEnemy(2);
+      const battle = await fightBattle(owner, enemyTemplate, 0);
+
+      await settleBattle(owner, enemyTemplate, battle);
+
+      await expectError(
+        program.methods
+          .closeBattle()
+          .accountsPartial({
+            caller: owner.authority.publicKey,
+            playerAuthority: admin.publicKey,
+            hero: owner.hero,
+            battle,
+          })
+          .signers([owner.authority])
+          .rpc(),
+        "Unauthorized"
+      );
+    });
+  });
+
+  describe("treasury reserve", () => {
+    const setReserve = (
+      reserve: number,
+      authority = admin.publicKey,
+      signers: anchor.web3.Signer[] = []
+    ) =>
+      program.methods
+        .setTreasuryReserve(new BN(reserve))
+        .accountsPartial({ authority, treasury })
+        .signers(signers)
+        .rpc();
+
+    it("keeps pending escrow in the vault whatever the reserve", async () => {
+      const owner = await createPlayerWithHero();
+      const enemyTemplate = await createWeakEnemy(3);
+      await startBattle(owner, enemyTemplate, 0);
+
+      // A reserve below the pending escrow is allowed, the escrow is never withdrawable
+      await setReserve(0);
+      const { pendingEscrow, bucketBalances } = await program.account.treasury.fetch(treasury);
+      expect(pendingEscrow.toNumber()).to.be.greaterThan(0);
+
+      const recipient = Keypair.generate().publicKey;
+      const withdraw = (amount: number) =>
+        program.methods
+          .withdrawTreasury(new BN(amount))
+          .accountsPartial({ authority: admin.publicKey, treasury, vault, recipient })
+          .rpc();
+
+      await expectError(withdraw(bucketBalances[0].toNumber() + 1), "InsufficientTreasuryFunds");
+      await withdraw(bucketBalances[0].toNumber());
+      expect(await connection.getBalance(vault)).to.be.at.least(pendingEscrow.toNumber());
+    });
+
+    it("withdraws only from the dev bucket, less the reserve it holds back", async () => {
+      const owner = await createPlayerWithHero();
+      const enemyTemplate = await createWeakEnemy(5);
+      await fightBattle(owner, enemyTemplate, 0);
+
+      // Hold 1000 lamports back beyond the pending escrow
+      const heldBack = 1000;
+      await setReserve((await program.account.treasury.fetch(treasury)).pendingEscrow.toNumber() + heldBack);
+      const { bucketBalances } = await program.account.treasury.fetch(treasury);
+      const withdrawable = bucketBalances[0].toNumber() - heldBack;
+
+      const recipient = Keypair.generate().publicKey;
+      const withdraw = (amount: number) =>
+        program.methods
+          .withdrawTreasury(new BN(amount))
+          .accountsPartial({ authority: admin.publicKey, treasury, vault, recipient })
+          .rpc();
+
+      await expectError(withdraw(withdrawable + 1), "InsufficientTreasuryFunds");
+      await withdraw(withdrawable);
+
+      expect(await connection.getBalance(recipient)).to.equal(withdrawable);
+      const updated = await program.account.treasury.fetch(treasury);
+      expect(updated.bucketBalances[0].toNumber()).to.equal(heldBack);
+      // Other buckets are untouched
+      expect(updated.bucketBalances.slice(1).map((balance) => balance.toNumber())).to.deep.equal(
+        bucketBalances.slice(1).map((balance) => balance.toNumber())
+      );
+    });
+
+    it("rejects reserve changes from anyone but the treasury authority", async () => {
+      const stranger = Keypair.generate();
+      await expectError(setReserve(0, stranger.publicKey, [stranger]), "Unauthorized");
+    });
+  });
+
+  describe("cancel pending", () => {
+    it("keeps the entry fee when the reveal hash is available", async () => {
+      const owner = await createPlayerWithHero();
+      const enemyTemplate = await createWeakEnemy(7);
+      const { battle } = await startBattle(owner, enemyTemplate, 0);
+      const { commitSlot, entryCost } = await program.account.battle.fetch(battle);
+      const before = await program.account.treasury.fetch(treasury);
+      const { totalSolSpent } = await program.account.player.fetch(owner.player);
+
+      // The reveal slot wasn't skipped, so the outcome could already be known
+      await waitForSlot(commitSlot.toNumber() + CANCEL_TIMEOUT_SLOTS + 1);
+      await cancelBattle(owner, battle);
+
+      expect(await connection.getAccountInfo(battle)).to.be.null;
+      const after = await program.account.treasury.fetch(treasury);
+      expect(before.pendingEscrow.sub(after.pendingEscrow).toNumber()).to.equal(entryCost.toNumber());
+      expect(after.bucketBalances[0].sub(before.bucketBalances[0]).toNumber()).to.equal(entryCost.toNumber());
+      const player = await program.account.player.fetch(owner.player);
+      expect(player.totalSolSpent.toNumber()).to.equal(totalSolSpent.toNumber());
+    });
+  });
+
+  describe("oracle randomness", () => {
+    const oracle = PublicKey.findProgramAddressSync([Buffer.from("oracle")], oracleProgram.programId)[0];
+    const requestFor = (requester: anchor.web3.PublicKey, commitSlot: BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("request"), requester.toBuffer(), commitSlot.toArrayLike(Buffer, "le", 8)],
+        oracleProgram.programId
+      )[0];
+
+    // Where the mock oracle keeps each request field
+    const mockLayout = {
+      discriminator: oracleProgram.idl.accounts.find((account) => account.name === "randomnessRequest")
+        .discriminator,
+      requesterOffset: 8,
+      requestSlotOffset: 40,
+      fulfilledOffset: 48,
+      randomnessOffset: 49,
+    };
+
+    const setProvider = (provider: number, oracleProgramId: anchor.web3.PublicKey) =>
+      program.methods
+        .setRandomnessProvider(provider, oracleProgramId, mockLayout)
+        .accountsPartial({ authority: admin.publicKey, gameConfig })
+        .rpc();
+
+    const requestRandomness = async (battle: anchor.web3.PublicKey) => {
+      const { commitSlot } = await program.account.battle.fetch(battle);
+      const request = requestFor(battle, commitSlot);
+      await oracleProgram.methods
+        .requestRandomness(battle, commitSlot)
+        .accountsPartial({ payer: admin.publicKey, oracle, request })
+        .rpc();
+      return request;
+    };
+
+    before(async () => {
+      await oracleProgram.methods
+        .initializeOracle()
+        .accountsPartial({ authority: admin.publicKey, oracle })
+        .rpc();
+      await setProvider(RANDOMNESS_PROVIDER_ORACLE, oracleProgram.programId);
+    });
+
+    after(async () => {
+      await setProvider(RANDOMNESS_PROVIDER_SLOT_HASHES, PublicKey.default);
+    });
+
+    it("seeds a battle from a fulfilled mock oracle request", async () => {
+      const owner = await createPlayerWithHero();
+      const enemyTemplate = await createWeakEnemy(8);
+      const { battle, secret } = await startBattle(owner, enemyTemplate, 0);
+      expect((await program.account.battle.fetch(battle)).oracleLayout.randomnessOffset).to.equal(49);
+      const request = await requestRandomness(battle);
+
+      const firstTurn = () =>
+        program.methods
+          .battleTurn(ACTION_ATTACK, Array.from(secret))
+          .accountsPartial({
+            playerAuthority: owner.authority.publicKey,
+            player: owner.player,
+            hero: owner.hero,
+            enemyTemplate,
+            battle,
+            treasury,
+            slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+            oracleRequest: request,
+            referrer: null,
+          })
+          .signers([owner.authority])
+          .rpc();
+
+      await expectError(firstTurn(), "OracleRandomnessPending");
+      await oracleProgram.methods
+        .fulfillRandomness(Array.from(randomBytes(32)))
+        .accountsPartial({ authority: admin.publicKey, oracle, request })
+        .rpc();
+      await firstTurn();
+
+      const { turn, rngSeed } = await program.account.battle.fetch(battle);
+      expect(turn).to.be.greaterThan(0);
+      expect(rngSeed).to.not.deep.equal(new Array(32).fill(0));
+    });
+
+    it("refunds a cancelled battle whose oracle request is unfulfilled", async () => {
+      const owner = await createPlayerWithHero();
+      const enemyTemplate = await createWeakEnemy(9);
+      const { battle } = await startBattle(owner, enemyTemplate, 0);
+      const request = await requestRandomness(battle);
+      const { commitSlot, entryCost } = await program.account.battle.fetch(battle);
+      const before = await program.account.treasury.fetch(treasury);
+      const { totalSolSpent } = await program.account.player.fetch(owner.player);
+
+      // Nothing was ever revealed, so the player can't know the outcome
+      await waitForSlot(commitSlot.toNumber() + CANCEL_TIMEOUT_SLOTS + 1);
+      await cancelBattle(owner, battle, request);
+
+      const after = await program.account.treasury.fetch(treasury);
+      expect(before.pendingEscrow.sub(after.pendingEscrow).toNumber()).to.equal(entryCost.toNumber());
+      expect(after.bucketBalances[0].toNumber()).to.equal(before.bucketBalances[0].toNumber());
+      const player = await program.account.player.fetch(owner.player);
+      expect(totalSolSpent.sub(player.totalSolSpent).toNumber()).to.equal(entryCost.toNumber());
+    });
+  });
+
+  describe("gold sinks", () => {
+    const ITEM_TYPE_WEAPON = 0;
+    const ELEMENT_FIRE = 1;
+    const ITEM_SHOP_COST = 50;
+    const GOLD_PER_VICTORY = 10; // Default gold per enemy level, the dummy is level 1
+
+    const setItemShopCost = async (itemShopCost: number) => {
+      const { combat, economy } = await program.account.gameConfig.fetch(gameConfig);
+      await program.methods
+        .updateGameConfig(combat, { ...economy, itemShopCost: new BN(itemShopCost) })
+        .accountsPartial({ authority: admin.publicKey, gameConfig })
+        .rpc();
+    };
+
+    const buyItem = ({ authority, player, hero }: Awaited<ReturnType<typeof createPlayerWithHero>>) =>
+      program.methods
+        .buyItem(ITEM_TYPE_WEAPON)
+        .accountsPartial({
+          playerAuthority: authority.publicKey,
+          player,
+          hero,
+          item: pda(Buffer.from("item"), hero.toBuffer(), u32(0)),
+          gameConfig,
+        })
+        .signers([authority])
+        .rpc();
+
+    const improveItem = (
+      method: "repairItem" | "upgradeItem",
+      { authority, player, hero }: Awaited<ReturnType<typeof createPlayerWithHero>>,
+      item: anchor.web3.PublicKey
+    ) =>
+      program.methods[method]()
+        .accountsPartial({ playerAuthority: authority.publicKey, player, hero, item, gameConfig })
+        .signers([authority])
+        .rpc();
+
+    after(async () => {
+      await setItemShopCost(ITEM_SHOP_COST);
+    });
+
+    it("spends battle gold on shop items and repairs of the wear they take", async () => {
+      const owner = await createPlayerWithHero();
+      const enemyTemplate = await createWeakEnemy(4);
+      const item = pda(Buffer.from("item"), owner.hero.toBuffer(), u32(0));
+
+      // Nothing earned yet
+      await expectError(buyItem(owner), "InsufficientGold");
+
+      await setItemShopCost(GOLD_PER_VICTORY);
+      await settleBattle(owner, enemyTemplate, await fightBattle(owner, enemyTemplate, 0));
+      await buyItem(owner);
+
+      let player = await program.account.player.fetch(owner.player);
+      expect(player.gold.toNumber()).to.equal(0);
+      expect(player.totalGoldSpent.toNumber()).to.equal(GOLD_PER_VICTORY);
+      expect((await program.account.item.fetch(item)).durability).to.equal(100);
+      await expectError(improveItem("repairItem", owner, item), "ItemNotDamaged");
+
+      await program.methods
+        .equipItem()
+        .accountsPartial({
+          playerAuthority: owner.authority.publicKey,
+          player: owner.player,
+          hero: owner.hero,
+          item,
+        })
+        .signers([owner.authority])
+        .rpc();
+
+      // The equipped weapon must be passed to start, and adds its bonus to the battle's snapshot
+      await expectError(startBattle(owner, enemyTemplate, 1), "ItemNotFound");
+      const battle = await fightBattle(owner, enemyTemplate, 1, item);
+      const { baseAttributes } = await program.account.hero.fetch(owner.hero);
+      const { heroAttributes } = await program.account.battle.fetch(battle);
+      expect(heroAttributes.strength).to.equal(baseAttributes.strength + 1);
+
+      // It must be passed to settle too, and wears down when it is
+      await expectError(settleBattle(owner, enemyTemplate, battle), "ItemNotFound");
+      await settleBattle(owner, enemyTemplate, battle, item);
+      expect((await program.account.item.fetch(item)).durability).to.equal(95);
+
+      // Repairs cost gold per point restored, upgrades are out of reach
+      await improveItem("repairItem", owner, item);
+      expect((await program.account.item.fetch(item)).durability).to.equal(100);
+      player = await program.account.player.fetch(owner.player);
+      expect(player.gold.toNumber()).to.equal(GOLD_PER_VICTORY - 5);
+      await expectError(improveItem("upgradeItem", owner, item), "InsufficientGold");
+    });
+
+    it("charges gold to change a hero's skill element", async () => {
+      const owner = await createPlayerWithHero();
+      const setSkillElement = (element: number) =>
+        program.methods
+          .setSkillElement(element)
+          .accountsPartial({
+            playerAuthority: owner.authority.publicKey,
+            player: owner.player,
+            hero: owner.hero,
+            gameConfig,
+          })
+          .signers([owner.authority])
+          .rpc();
+
+      await expectError(setSkillElement(ELEMENT_FIRE), "InsufficientGold");
+      const { elemental } = await program.account.hero.fetch(owner.hero);
+      expect(elemental.element).to.equal(0);
+    });
+  });
+
+  describe("referrals", () => {
+    const REFERRAL_BPS = 500;
+    const HERO_PURCHASE_COST = LAMPORTS_PER_SOL;
+    const BATTLE_ENTRY_COST = 50_000_000;
+    const reward = (spent: number) => (spent * REFERRAL_BPS) / 10_000;
+
+    it("pays referrers off the top of SOL fees with the default split", async () => {
+      const referrer = await createPlayerWithHero();
+      const { revenueShares, bucketBalances } = await program.account.treasury.fetch(treasury);
+      expect(revenueShares).to.deep.equal([10_000, 0, 0, 0]);
+
+      // The referral pool is empty by default, the reward doesn't depend on it
+      const referee = await createPlayerWithHero(referrer.player);
+      let referrerAccount = await program.account.player.fetch(referrer.player);
+      expect(referrerAccount.referralBalance.toNumber()).to.equal(reward(HERO_PURCHASE_COST));
+      expect(referrerAccount.refereeSolSpent.toNumber()).to.equal(HERO_PURCHASE_COST);
+
+      const afterHero = await program.account.treasury.fetch(treasury);
+      expect(afterHero.bucketBalances[0].sub(bucketBalances[0]).toNumber()).to.equal(
+        HERO_PURCHASE_COST - reward(HERO_PURCHASE_COST)
+      );
+
+      // Entry fees pay the referrer when they resolve, the referrer must be passed then
+      const enemyTemplate = await createWeakEnemy(6);
+      const { battle, secret } = await startBattle(referee, enemyTemplate, 0);
+      const { commitSlot } = await program.account.battle.fetch(battle);
+      await waitForSlot(commitSlot.toNumber() + MIN_RANDOMNESS_DELAY_SLOTS + 1);
+      await expectError(
+        program.methods
+          .battleTurn(ACTION_ATTACK, Array.from(secret))
+          .accountsPartial({
+            playerAuthority: referee.authority.publicKey,
+            player: referee.player,
+            hero: referee.hero,
+            enemyTemplate,
+            battle,
+            treasury,
+            slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+            oracleRequest: null,
+            referrer: null,
+          })
+          .signers([referee.authority])
+          .rpc(),
+        "InvalidReferrer"
+      );
+
+      await fightBattle(referee, enemyTemplate, 1);
+      referrerAccount = await program.account.player.fetch(referrer.player);
+      expect(referrerAccount.referralBalance.toNumber()).to.equal(
+        reward(HERO_PURCHASE_COST) + reward(BATTLE_ENTRY_COST)
+      );
+      expect(referrerAccount.refereeSolSpent.toNumber()).to.equal(
+        HERO_PURCHASE_COST + BATTLE_ENTRY_COST
+      );
+    });
   });
 });